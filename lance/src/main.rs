@@ -1,7 +1,9 @@
 extern crate steed;
 use steed::board::Board;
+use steed::eval::explain;
 
 fn main() {
     let board = Board::default();
-    println!("{:?}", board);
+    println!("{}", board);
+    println!("{}", explain(&board));
 }
@@ -0,0 +1,259 @@
+use std::sync::OnceLock;
+
+use board::{Board, Color, Piece, PieceKind};
+use position::Position;
+use square::{Square, SquareSet};
+
+const KNIGHT: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+const KING: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+const DIAGONALS: [(i8, i8); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+const STRAIGHTS: [(i8, i8); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+
+// What knights, kings and pawns of either color attack from each square,
+// worked out once since it never changes.
+struct Leapers {
+    knight: [SquareSet; 64],
+    king: [SquareSet; 64],
+    pawn: [[SquareSet; 64]; 2],
+}
+
+static LEAPERS: OnceLock<Leapers> = OnceLock::new();
+
+fn leapers() -> &'static Leapers {
+    LEAPERS.get_or_init(|| {
+        let mut leapers = Leapers {
+            knight: [SquareSet::new(); 64],
+            king: [SquareSet::new(); 64],
+            pawn: [[SquareSet::new(); 64]; 2],
+        };
+        for square in Square::all() {
+            let i = square.index();
+            leapers.knight[i] = leaps(square, &KNIGHT);
+            leapers.king[i] = leaps(square, &KING);
+            leapers.pawn[Color::White as usize][i] = leaps(square, &[(-1, 1), (1, 1)]);
+            leapers.pawn[Color::Black as usize][i] = leaps(square, &[(-1, -1), (1, -1)]);
+        }
+        leapers
+    })
+}
+
+/// The squares a pawn of `color` on `square` attacks.
+pub fn pawn_attacks(square: Square, color: Color) -> SquareSet {
+    leapers().pawn[color as usize][square.index()]
+}
+
+pub fn knight_attacks(square: Square) -> SquareSet {
+    leapers().knight[square.index()]
+}
+
+/// The squares next to `square`.
+pub fn king_attacks(square: Square) -> SquareSet {
+    leapers().king[square.index()]
+}
+
+fn leaps(square: Square, offsets: &[(i8, i8)]) -> SquareSet {
+    offsets
+        .iter()
+        .filter_map(|&(files, ranks)| square.offset(files, ranks))
+        .collect()
+}
+
+impl Board {
+    /// The squares the piece on `square` attacks, stopping sliders at the
+    /// first piece in each direction. Squares held by the piece's own side
+    /// are included, since it defends them.
+    pub fn attacks_from(&self, square: Square) -> SquareSet {
+        let piece = self.piece_at(square);
+        match piece.kind() {
+            Some(PieceKind::Pawn) => pawn_attacks(square, piece.color()),
+            Some(PieceKind::Knight) => knight_attacks(square),
+            Some(PieceKind::Bishop) => self.slides(square, &DIAGONALS),
+            Some(PieceKind::Rook) => self.slides(square, &STRAIGHTS),
+            Some(PieceKind::Queen) => self
+                .slides(square, &DIAGONALS)
+                .union(self.slides(square, &STRAIGHTS)),
+            Some(PieceKind::King) => king_attacks(square),
+            None => SquareSet::new(),
+        }
+    }
+
+    /// Every square attacked by at least one of `color`'s pieces.
+    pub fn attacked_squares(&self, color: Color) -> SquareSet {
+        self.pieces(color)
+            .into_iter()
+            .fold(SquareSet::new(), |attacked, (position, _)| {
+                attacked.union(self.attacks_from(Square::from(position)))
+            })
+    }
+
+    /// The squares holding one of `color`'s pieces.
+    pub fn occupied_by(&self, color: Color) -> SquareSet {
+        self.pieces(color)
+            .into_iter()
+            .map(|(position, _)| Square::from(position))
+            .collect()
+    }
+
+    /// Whether any piece of `color` attacks `position`.
+    pub fn attacked_by<P: Into<Position>>(&self, position: P, color: Color) -> bool {
+        !self.attackers_of(position, color).is_empty()
+    }
+
+    /// Every square holding a piece of `color` that attacks `position`.
+    /// Attacks are symmetric, so these are found by looking out from
+    /// `position` the way each kind of piece would.
+    pub fn attackers_of<P: Into<Position>>(&self, position: P, color: Color) -> Vec<Position> {
+        let target = Square::from(position.into());
+        let diagonals = self.slides(target, &DIAGONALS);
+        let straights = self.slides(target, &STRAIGHTS);
+
+        let lines = [
+            (pawn_attacks(target, color.opposite()), PieceKind::Pawn),
+            (knight_attacks(target), PieceKind::Knight),
+            (diagonals, PieceKind::Bishop),
+            (straights, PieceKind::Rook),
+            (diagonals.union(straights), PieceKind::Queen),
+            (king_attacks(target), PieceKind::King),
+        ];
+
+        lines
+            .iter()
+            .flat_map(|&(squares, kind)| {
+                let piece = Piece::new(kind, color);
+                squares
+                    .iter()
+                    .filter(move |&square| self.piece_at(square) == piece)
+            })
+            .map(Position::from)
+            .collect()
+    }
+
+    fn slides(&self, square: Square, directions: &[(i8, i8)]) -> SquareSet {
+        let mut attacks = SquareSet::new();
+        for &(files, ranks) in directions {
+            let mut next = square.offset(files, ranks);
+            while let Some(target) = next {
+                attacks.insert(target);
+                if self.piece_at(target).kind().is_some() {
+                    break;
+                }
+                next = target.offset(files, ranks);
+            }
+        }
+        attacks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn squares(names: &[&str]) -> SquareSet {
+        names.iter().map(|name| name.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn piece_attacks() {
+        let board = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . p . .
+            . . . . . . . .
+            . . . R . . . .
+            . . . . . . . .
+            . . . P . . . .
+            . N . . K . . B
+            "
+        );
+
+        assert_eq!(
+            board.attacks_from("d4".parse().unwrap()),
+            squares(&[
+                "d5", "d6", "d7", "d8", "e4", "f4", "g4", "h4", "d3", "d2", "c4", "b4", "a4"
+            ])
+        );
+        assert_eq!(
+            board.attacks_from("h1".parse().unwrap()),
+            squares(&["g2", "f3", "e4", "d5", "c6", "b7", "a8"])
+        );
+        assert_eq!(
+            board.attacks_from("b1".parse().unwrap()),
+            squares(&["a3", "c3", "d2"])
+        );
+        assert_eq!(
+            board.attacks_from("d2".parse().unwrap()),
+            squares(&["c3", "e3"])
+        );
+        assert_eq!(
+            board.attacks_from("f6".parse().unwrap()),
+            squares(&["e5", "g5"])
+        );
+        assert_eq!(board.attacks_from("a1".parse().unwrap()), SquareSet::new());
+    }
+
+    #[test]
+    fn attacked_and_occupied() {
+        let board = Board::default();
+
+        let attacked = board.attacked_squares(Color::White);
+        assert_eq!(attacked.len(), 22);
+        assert!(attacked.contains("f3".parse().unwrap()));
+        assert!(!attacked.contains("e4".parse().unwrap()));
+
+        assert_eq!(board.occupied_by(Color::Black).len(), 16);
+        assert!(board
+            .occupied_by(Color::Black)
+            .contains("e8".parse().unwrap()));
+    }
+
+    #[test]
+    fn attackers_match_attacks() {
+        let board = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . p . .
+            . . . . . . . .
+            . . . R . . . .
+            . . . . . . . .
+            . . . P . . . .
+            . N . . K . . B
+            "
+        );
+
+        for target in Square::all() {
+            for &color in &[Color::White, Color::Black] {
+                let attackers: SquareSet = board
+                    .attackers_of(target, color)
+                    .into_iter()
+                    .map(Square::from)
+                    .collect();
+                let attacking: SquareSet = board
+                    .occupied_by(color)
+                    .iter()
+                    .filter(|&square| board.attacks_from(square).contains(target))
+                    .collect();
+                assert_eq!(attackers, attacking, "{} by {:?}", target, color);
+            }
+        }
+    }
+}
@@ -5,7 +5,7 @@ use std::iter::Enumerate;
 
 use std::slice::Iter;
 
-use position::Position;
+use position::{Column, Position};
 use movement::Move;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -27,7 +27,72 @@ pub enum Piece {
     Empty,
 }
 
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum PieceKind {
+    Pawn,
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+    King,
+}
+
+impl PieceKind {
+    /// Material value in pawns; the king has none.
+    pub fn value(self) -> u32 {
+        match self {
+            PieceKind::Pawn => 1,
+            PieceKind::Knight | PieceKind::Bishop => 3,
+            PieceKind::Rook => 5,
+            PieceKind::Queen => 9,
+            PieceKind::King => 0,
+        }
+    }
+
+    /// The uppercase letter used in SAN and material signatures.
+    pub fn letter(self) -> char {
+        match self {
+            PieceKind::Pawn => 'P',
+            PieceKind::Knight => 'N',
+            PieceKind::Bishop => 'B',
+            PieceKind::Rook => 'R',
+            PieceKind::Queen => 'Q',
+            PieceKind::King => 'K',
+        }
+    }
+}
+
 impl Piece {
+    pub fn new(kind: PieceKind, color: Color) -> Piece {
+        match (kind, color) {
+            (PieceKind::Pawn, Color::White) => Piece::WhitePawn,
+            (PieceKind::Knight, Color::White) => Piece::WhiteKnight,
+            (PieceKind::Bishop, Color::White) => Piece::WhiteBishop,
+            (PieceKind::Rook, Color::White) => Piece::WhiteRook,
+            (PieceKind::Queen, Color::White) => Piece::WhiteQueen,
+            (PieceKind::King, Color::White) => Piece::WhiteKing,
+            (PieceKind::Pawn, Color::Black) => Piece::BlackPawn,
+            (PieceKind::Knight, Color::Black) => Piece::BlackKnight,
+            (PieceKind::Bishop, Color::Black) => Piece::BlackBishop,
+            (PieceKind::Rook, Color::Black) => Piece::BlackRook,
+            (PieceKind::Queen, Color::Black) => Piece::BlackQueen,
+            (PieceKind::King, Color::Black) => Piece::BlackKing,
+        }
+    }
+
+    /// `None` for an empty square.
+    pub fn kind(self) -> Option<PieceKind> {
+        match self {
+            Piece::WhitePawn | Piece::BlackPawn => Some(PieceKind::Pawn),
+            Piece::WhiteKnight | Piece::BlackKnight => Some(PieceKind::Knight),
+            Piece::WhiteBishop | Piece::BlackBishop => Some(PieceKind::Bishop),
+            Piece::WhiteRook | Piece::BlackRook => Some(PieceKind::Rook),
+            Piece::WhiteQueen | Piece::BlackQueen => Some(PieceKind::Queen),
+            Piece::WhiteKing | Piece::BlackKing => Some(PieceKind::King),
+            Piece::Empty => None,
+        }
+    }
+
     pub fn color(self) -> Color {
         match self {
             Piece::WhitePawn
@@ -40,6 +105,73 @@ impl Piece {
             _ => Color::Black,
         }
     }
+
+    /// The piece's letter as written in FEN: uppercase for white, lowercase
+    /// for black and `.` for an empty square.
+    pub fn letter(self) -> char {
+        match self {
+            Piece::WhitePawn => 'P',
+            Piece::WhiteRook => 'R',
+            Piece::WhiteKnight => 'N',
+            Piece::WhiteBishop => 'B',
+            Piece::WhiteKing => 'K',
+            Piece::WhiteQueen => 'Q',
+
+            Piece::BlackPawn => 'p',
+            Piece::BlackRook => 'r',
+            Piece::BlackKnight => 'n',
+            Piece::BlackBishop => 'b',
+            Piece::BlackKing => 'k',
+            Piece::BlackQueen => 'q',
+
+            Piece::Empty => '.',
+        }
+    }
+
+    /// The piece's Unicode chess symbol, or `.` for an empty square.
+    pub fn glyph(self) -> char {
+        match self {
+            Piece::WhitePawn => '\u{2659}',
+            Piece::WhiteRook => '\u{2656}',
+            Piece::WhiteKnight => '\u{2658}',
+            Piece::WhiteBishop => '\u{2657}',
+            Piece::WhiteKing => '\u{2654}',
+            Piece::WhiteQueen => '\u{2655}',
+
+            Piece::BlackPawn => '\u{265F}',
+            Piece::BlackRook => '\u{265C}',
+            Piece::BlackKnight => '\u{265E}',
+            Piece::BlackBishop => '\u{265D}',
+            Piece::BlackKing => '\u{265A}',
+            Piece::BlackQueen => '\u{265B}',
+
+            Piece::Empty => '.',
+        }
+    }
+
+    /// Reads a FEN letter or Unicode chess symbol; `.` is an empty square.
+    pub fn from_char(c: char) -> Option<Piece> {
+        let piece = match c {
+            'P' | '\u{2659}' => Piece::WhitePawn,
+            'R' | '\u{2656}' => Piece::WhiteRook,
+            'N' | '\u{2658}' => Piece::WhiteKnight,
+            'B' | '\u{2657}' => Piece::WhiteBishop,
+            'K' | '\u{2654}' => Piece::WhiteKing,
+            'Q' | '\u{2655}' => Piece::WhiteQueen,
+
+            'p' | '\u{265F}' => Piece::BlackPawn,
+            'r' | '\u{265C}' => Piece::BlackRook,
+            'n' | '\u{265E}' => Piece::BlackKnight,
+            'b' | '\u{265D}' => Piece::BlackBishop,
+            'k' | '\u{265A}' => Piece::BlackKing,
+            'q' | '\u{265B}' => Piece::BlackQueen,
+
+            '.' => Piece::Empty,
+            _ => return None,
+        };
+
+        Some(piece)
+    }
 }
 
 #[derive(Clone)]
@@ -89,12 +221,83 @@ pub enum Color {
     Black,
 }
 
+impl Color {
+    pub fn opposite(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct CastlingRights {
+    pub white_kingside: bool,
+    pub white_queenside: bool,
+    pub black_kingside: bool,
+    pub black_queenside: bool,
+}
+
+impl CastlingRights {
+    pub fn all() -> CastlingRights {
+        CastlingRights {
+            white_kingside: true,
+            white_queenside: true,
+            black_kingside: true,
+            black_queenside: true,
+        }
+    }
+
+    pub fn none() -> CastlingRights {
+        CastlingRights {
+            white_kingside: false,
+            white_queenside: false,
+            black_kingside: false,
+            black_queenside: false,
+        }
+    }
+
+    pub fn kingside(&self, color: Color) -> bool {
+        match color {
+            Color::White => self.white_kingside,
+            Color::Black => self.black_kingside,
+        }
+    }
+
+    pub fn queenside(&self, color: Color) -> bool {
+        match color {
+            Color::White => self.white_queenside,
+            Color::Black => self.black_queenside,
+        }
+    }
+
+    // Moving from or onto a king or rook home square gives up the matching
+    // rights, which covers king moves, rook moves and rooks being captured.
+    fn touch(&mut self, position: Position) {
+        match (position.column, position.row) {
+            (Column::E, 1) => {
+                self.white_kingside = false;
+                self.white_queenside = false;
+            }
+            (Column::H, 1) => self.white_kingside = false,
+            (Column::A, 1) => self.white_queenside = false,
+            (Column::E, 8) => {
+                self.black_kingside = false;
+                self.black_queenside = false;
+            }
+            (Column::H, 8) => self.black_kingside = false,
+            (Column::A, 8) => self.black_queenside = false,
+            _ => {}
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Board {
     layout: BoardLayout,
     history: Vec<Move>,
-    white_can_castle: bool,
-    black_can_castle: bool,
+    castling: CastlingRights,
+    en_passant: Option<Position>,
     pub to_move: Color,
 }
 
@@ -103,8 +306,8 @@ impl Board {
         Board {
             to_move: Color::White,
             history: Vec::new(),
-            white_can_castle: true,
-            black_can_castle: true,
+            castling: CastlingRights::all(),
+            en_passant: None,
             layout: BoardLayout {
                 layout: [
                     Piece::Empty,
@@ -192,22 +395,85 @@ impl Board {
         self.layout.add_piece(piece, position);
     }
 
+    /// Every move played on this board since it was set up, in order.
+    pub fn history(&self) -> &[Move] {
+        &self.history
+    }
+
+    pub fn castling(&self) -> CastlingRights {
+        self.castling
+    }
+
+    /// The square a pawn skipped over on the previous move, if any.
+    pub fn en_passant(&self) -> Option<Position> {
+        self.en_passant
+    }
+
+    pub fn set_castling(&mut self, castling: CastlingRights) {
+        self.castling = castling;
+    }
+
+    pub fn set_en_passant(&mut self, en_passant: Option<Position>) {
+        self.en_passant = en_passant;
+    }
+
     pub fn move_piece<P: Into<Position>>(&mut self, start: P, end: P) {
         let start = start.into();
         let piece = self.piece_at(start);
 
-        self.add_piece(Piece::Empty, start);
-        self.add_piece(piece, end);
+        self.make_move(Move::new(start, end.into(), piece));
+    }
+
+    /// Plays `m` on the board without checking that it is legal, taking care
+    /// of the rook when castling, the captured pawn when taking en passant and
+    /// the new piece when promoting.
+    pub fn make_move(&mut self, m: Move) {
+        let start_column: i8 = m.start.column.into();
+        let end_column: i8 = m.end.column.into();
+
+        match m.piece {
+            Piece::WhitePawn | Piece::BlackPawn
+                if start_column != end_column && self.piece_at(m.end) == Piece::Empty =>
+            {
+                self.add_piece(Piece::Empty, Position::new(m.end.column, m.start.row));
+            }
+
+            Piece::WhiteKing | Piece::BlackKing if (end_column - start_column).abs() == 2 => {
+                let (from, to) = if end_column > start_column {
+                    (Column::H, Column::F)
+                } else {
+                    (Column::A, Column::D)
+                };
+                let from = Position::new(from, m.start.row);
+                let rook = self.piece_at(from);
+
+                self.add_piece(Piece::Empty, from);
+                self.add_piece(rook, Position::new(to, m.start.row));
+            }
+
+            _ => {}
+        }
+
+        self.en_passant = match m.piece {
+            Piece::WhitePawn | Piece::BlackPawn
+                if (m.end.row as i8 - m.start.row as i8).abs() == 2 =>
+            {
+                Some(Position::new(m.start.column, (m.start.row + m.end.row) / 2))
+            }
+            _ => None,
+        };
+
+        self.castling.touch(m.start);
+        self.castling.touch(m.end);
+
+        self.add_piece(Piece::Empty, m.start);
+        self.add_piece(m.promotion.unwrap_or(m.piece), m.end);
+        self.history.push(m);
         self.alternate_to_move();
-        //self.record_move(piece, start, end);
     }
 
     fn alternate_to_move(&mut self) {
-        if self.to_move == Color::Black {
-            self.to_move = Color::White;
-        } else {
-            self.to_move = Color::Black;
-        }
+        self.to_move = self.to_move.opposite();
     }
 
     pub fn threefold_draw(&self) -> bool {
@@ -229,19 +495,8 @@ impl Board {
     }
 
     pub fn in_check(&self, color: Color) -> bool {
-        if let Some(position) = self.find_king(color) {
-            for (i, piece) in self.enumerate_pieces() {
-                if piece.color() != color {
-                    let i: Position = i.into();
-                    for m in self.moves_for(i) {
-                        if m == position {
-                            return true;
-                        }
-                    }
-                }
-
-                continue;
-            }
+        if let Some(position) = self.king_square(color) {
+            return self.attacked_by(position, color.opposite());
         }
 
         false
@@ -281,20 +536,61 @@ impl Board {
         true
     }
 
-    fn find_king(&self, color: Color) -> Option<Position> {
-        for (i, piece) in self.enumerate_pieces() {
-            if piece.color() == color {
-                match *piece {
-                    Piece::BlackKing | Piece::WhiteKing => {
-                        let position: Position = i.into();
-                        return Some(position);
-                    }
-                    _ => continue,
-                }
-            }
-        }
+    /// Every piece of `color` with its square, from a8 to h1.
+    pub fn pieces(&self, color: Color) -> Vec<(Position, Piece)> {
+        self.enumerate_pieces()
+            .filter(|&(_, &piece)| piece != Piece::Empty && piece.color() == color)
+            .map(|(i, &piece)| (Position::from(i), piece))
+            .collect()
+    }
+
+    pub fn squares_of(&self, kind: PieceKind, color: Color) -> Vec<Position> {
+        let wanted = Piece::new(kind, color);
+        self.enumerate_pieces()
+            .filter(|&(_, &piece)| piece == wanted)
+            .map(|(i, _)| Position::from(i))
+            .collect()
+    }
 
-        None
+    pub fn king_square(&self, color: Color) -> Option<Position> {
+        let king = Piece::new(PieceKind::King, color);
+        self.enumerate_pieces()
+            .find(|&(_, &piece)| piece == king)
+            .map(|(i, _)| Position::from(i))
+    }
+
+    /// The value of `color`'s pieces in pawns, not counting the king.
+    pub fn material(&self, color: Color) -> u32 {
+        self.pieces(color)
+            .iter()
+            .filter_map(|&(_, piece)| piece.kind())
+            .map(PieceKind::value)
+            .sum()
+    }
+
+    /// Both sides' pieces from king down to pawns, white first, as in
+    /// `KRPvKR`.
+    pub fn material_signature(&self) -> String {
+        let kinds = [
+            PieceKind::King,
+            PieceKind::Queen,
+            PieceKind::Rook,
+            PieceKind::Bishop,
+            PieceKind::Knight,
+            PieceKind::Pawn,
+        ];
+
+        let side = |color: Color| -> String {
+            kinds
+                .iter()
+                .flat_map(|&kind| {
+                    let count = self.squares_of(kind, color).len();
+                    std::iter::repeat_n(kind.letter(), count)
+                })
+                .collect()
+        };
+
+        format!("{}v{}", side(Color::White), side(Color::Black))
     }
 }
 
@@ -371,8 +667,8 @@ impl Default for Board {
             layout: BoardLayout { layout: board },
             to_move: Color::White,
             history: Vec::new(),
-            white_can_castle: true,
-            black_can_castle: true,
+            castling: CastlingRights::all(),
+            en_passant: None,
         }
     }
 }
@@ -394,8 +690,8 @@ mod tests {
         let board = Board {
             to_move: Color::White,
             history: Vec::new(),
-            white_can_castle: true,
-            black_can_castle: true,
+            castling: CastlingRights::all(),
+            en_passant: None,
             layout: BoardLayout {
                 layout: [
                     BlackRook,
@@ -520,6 +816,41 @@ mod tests {
         assert!(false);
     }
 
+    #[test]
+    fn piece_queries() {
+        let mut board = Board::default();
+        board.move_piece("e2", "e4");
+
+        assert_eq!(board.pieces(Color::White).len(), 16);
+        assert_eq!(board.pieces(Color::Black)[0], ("a8".into(), BlackRook));
+        assert_eq!(
+            board.squares_of(PieceKind::Knight, Color::White),
+            vec!["b1".into(), "g1".into()]
+        );
+        assert!(board
+            .squares_of(PieceKind::Pawn, Color::White)
+            .contains(&"e4".into()));
+        assert_eq!(board.king_square(Color::Black), Some("e8".into()));
+        assert_eq!(Board::empty().king_square(Color::White), None);
+    }
+
+    #[test]
+    fn material() {
+        let board = Board::default();
+        assert_eq!(board.material(Color::White), 39);
+        assert_eq!(board.material(Color::Black), 39);
+        assert_eq!(board.material_signature(), "KQRRBBNNPPPPPPPPvKQRRBBNNPPPPPPPP");
+
+        let mut board = Board::empty();
+        board.add_piece(WhiteKing, "g1");
+        board.add_piece(WhitePawn, "g2");
+        board.add_piece(WhiteRook, "a1");
+        board.add_piece(BlackKing, "g8");
+        board.add_piece(BlackRook, "a8");
+        assert_eq!(board.material(Color::White), 6);
+        assert_eq!(board.material_signature(), "KRPvKR");
+    }
+
     #[test]
     fn recording_the_game() {
         let mut board = Board::empty();
@@ -527,6 +858,14 @@ mod tests {
         board.move_piece("a2", "a3");
         assert_eq!(board.history.len(), 1);
 
-        assert!(false);
+        board.add_piece(Piece::BlackKing, "h8");
+        board.move_piece("h8", "g8");
+        assert_eq!(
+            board.history(),
+            &[
+                Move::new("a2".into(), "a3".into(), Piece::WhitePawn),
+                Move::new("h8".into(), "g8".into(), Piece::BlackKing),
+            ]
+        );
     }
 }
@@ -0,0 +1,282 @@
+use std::fmt;
+
+use board::{Board, CastlingRights, Color, Piece, PieceKind};
+use position::{Column, Position};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum SetupError {
+    /// A side doesn't have exactly one king.
+    KingCount(Color),
+    /// A pawn is on the first or eighth rank.
+    PawnOnBackRank(Position),
+    /// The side that just moved has been left in check.
+    OpponentInCheck,
+    /// The side to move is checked by pieces that could not all have
+    /// given check with a single move.
+    ImpossibleCheck,
+    /// A castling right is set but the king or rook is not at home.
+    CastlingRights,
+    /// The en passant square doesn't follow a double pawn push.
+    EnPassant(Position),
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SetupError::KingCount(color) => write!(f, "{:?} must have exactly one king", color),
+            SetupError::PawnOnBackRank(position) => write!(f, "pawn on back rank at {}", position),
+            SetupError::OpponentInCheck => write!(f, "side not to move is in check"),
+            SetupError::ImpossibleCheck => write!(f, "impossible check"),
+            SetupError::CastlingRights => write!(f, "castling rights don't match the position"),
+            SetupError::EnPassant(position) => write!(f, "invalid en passant square {}", position),
+        }
+    }
+}
+
+/// Collects a position piece by piece and checks that it could arise in a
+/// game before handing out a `Board`. Unlike `Board::empty`, no castling
+/// rights are assumed.
+#[derive(Debug, Clone)]
+pub struct BoardBuilder {
+    board: Board,
+}
+
+impl BoardBuilder {
+    pub fn new() -> BoardBuilder {
+        let mut board = Board::empty();
+        board.set_castling(CastlingRights::none());
+
+        BoardBuilder { board }
+    }
+
+    pub fn piece<P: Into<Position>>(mut self, piece: Piece, position: P) -> BoardBuilder {
+        self.board.add_piece(piece, position);
+        self
+    }
+
+    pub fn to_move(mut self, color: Color) -> BoardBuilder {
+        self.board.to_move = color;
+        self
+    }
+
+    pub fn castling(mut self, castling: CastlingRights) -> BoardBuilder {
+        self.board.set_castling(castling);
+        self
+    }
+
+    pub fn en_passant<P: Into<Position>>(mut self, position: P) -> BoardBuilder {
+        self.board.set_en_passant(Some(position.into()));
+        self
+    }
+
+    pub fn build(self) -> Result<Board, SetupError> {
+        let board = self.board;
+
+        for &color in &[Color::White, Color::Black] {
+            if board.squares_of(PieceKind::King, color).len() != 1 {
+                return Err(SetupError::KingCount(color));
+            }
+        }
+
+        for &color in &[Color::White, Color::Black] {
+            for position in board.squares_of(PieceKind::Pawn, color) {
+                if position.row == 1 || position.row == 8 {
+                    return Err(SetupError::PawnOnBackRank(position));
+                }
+            }
+        }
+
+        if board.in_check(board.to_move.opposite()) {
+            return Err(SetupError::OpponentInCheck);
+        }
+
+        check_checkers(&board)?;
+        check_castling(&board)?;
+
+        if let Some(position) = board.en_passant() {
+            check_en_passant(&board, position)?;
+        }
+
+        Ok(board)
+    }
+}
+
+impl Default for BoardBuilder {
+    fn default() -> BoardBuilder {
+        BoardBuilder::new()
+    }
+}
+
+// A single move gives at most two checks, and the second one must be
+// discovered, so it comes from a bishop, rook or queen.
+fn check_checkers(board: &Board) -> Result<(), SetupError> {
+    let color = board.to_move;
+    let checkers = match board.king_square(color) {
+        Some(king) => board.attackers_of(king, color.opposite()),
+        None => return Ok(()),
+    };
+
+    let sliders = checkers
+        .iter()
+        .filter(|&&position| is_slider(board.piece_at(position)))
+        .count();
+
+    match checkers.len() {
+        0 | 1 => Ok(()),
+        2 if sliders > 0 => Ok(()),
+        _ => Err(SetupError::ImpossibleCheck),
+    }
+}
+
+fn check_castling(board: &Board) -> Result<(), SetupError> {
+    let rights = board.castling();
+    let at_home =
+        |piece: Piece, column: Column, row: u8| board.piece_at(Position::new(column, row)) == piece;
+
+    let white_king = at_home(Piece::WhiteKing, Column::E, 1);
+    let black_king = at_home(Piece::BlackKing, Column::E, 8);
+    let valid = (!rights.white_kingside || white_king && at_home(Piece::WhiteRook, Column::H, 1))
+        && (!rights.white_queenside || white_king && at_home(Piece::WhiteRook, Column::A, 1))
+        && (!rights.black_kingside || black_king && at_home(Piece::BlackRook, Column::H, 8))
+        && (!rights.black_queenside || black_king && at_home(Piece::BlackRook, Column::A, 8));
+
+    if valid {
+        Ok(())
+    } else {
+        Err(SetupError::CastlingRights)
+    }
+}
+
+// The pawn that just moved two squares sits in front of the en passant
+// square, and both the square and the one it came from are empty.
+fn check_en_passant(board: &Board, position: Position) -> Result<(), SetupError> {
+    let (row, pawn, pawn_row, start_row) = match board.to_move {
+        Color::White => (6, Piece::BlackPawn, 5, 7),
+        Color::Black => (3, Piece::WhitePawn, 4, 2),
+    };
+
+    let valid = position.row == row
+        && board.piece_at(position) == Piece::Empty
+        && board.piece_at(Position::new(position.column, start_row)) == Piece::Empty
+        && board.piece_at(Position::new(position.column, pawn_row)) == pawn;
+
+    if valid {
+        Ok(())
+    } else {
+        Err(SetupError::EnPassant(position))
+    }
+}
+
+fn is_slider(piece: Piece) -> bool {
+    matches!(
+        piece,
+        Piece::WhiteBishop
+            | Piece::WhiteRook
+            | Piece::WhiteQueen
+            | Piece::BlackBishop
+            | Piece::BlackRook
+            | Piece::BlackQueen
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kings() -> BoardBuilder {
+        BoardBuilder::new()
+            .piece(Piece::WhiteKing, "e1")
+            .piece(Piece::BlackKing, "e8")
+    }
+
+    #[test]
+    fn build_a_position() {
+        let board = kings()
+            .piece(Piece::WhiteRook, "h1")
+            .piece(Piece::BlackPawn, "d5")
+            .to_move(Color::Black)
+            .castling(CastlingRights {
+                white_kingside: true,
+                ..CastlingRights::none()
+            })
+            .build()
+            .unwrap();
+
+        assert_eq!(board.piece_at("h1"), Piece::WhiteRook);
+        assert_eq!(board.to_move, Color::Black);
+        assert!(board.castling().white_kingside);
+        assert!(!board.castling().white_queenside);
+        assert!(board.history().is_empty());
+    }
+
+    #[test]
+    fn king_counts() {
+        let board = BoardBuilder::new().piece(Piece::WhiteKing, "e1").build();
+        assert_eq!(board, Err(SetupError::KingCount(Color::Black)));
+
+        let board = kings().piece(Piece::WhiteKing, "a1").build();
+        assert_eq!(board, Err(SetupError::KingCount(Color::White)));
+    }
+
+    #[test]
+    fn pawn_ranks() {
+        let board = kings().piece(Piece::BlackPawn, "c1").build();
+        assert_eq!(board, Err(SetupError::PawnOnBackRank("c1".into())));
+
+        let board = kings().piece(Piece::WhitePawn, "c8").build();
+        assert_eq!(board, Err(SetupError::PawnOnBackRank("c8".into())));
+    }
+
+    #[test]
+    fn checks() {
+        let board = kings()
+            .piece(Piece::WhiteRook, "e4")
+            .to_move(Color::Black)
+            .build();
+        assert!(board.is_ok());
+
+        let board = kings().piece(Piece::WhiteRook, "e4").build();
+        assert_eq!(board, Err(SetupError::OpponentInCheck));
+
+        let board = kings()
+            .piece(Piece::BlackKnight, "d3")
+            .piece(Piece::BlackKnight, "f3")
+            .build();
+        assert_eq!(board, Err(SetupError::ImpossibleCheck));
+
+        let board = kings()
+            .piece(Piece::BlackKnight, "d3")
+            .piece(Piece::BlackRook, "e4")
+            .build();
+        assert!(board.is_ok());
+    }
+
+    #[test]
+    fn castling_rights() {
+        let board = kings().castling(CastlingRights::all()).build();
+        assert_eq!(board, Err(SetupError::CastlingRights));
+
+        let board = kings()
+            .piece(Piece::BlackRook, "a8")
+            .castling(CastlingRights {
+                black_queenside: true,
+                ..CastlingRights::none()
+            })
+            .build();
+        assert!(board.is_ok());
+    }
+
+    #[test]
+    fn en_passant() {
+        let builder = kings().piece(Piece::WhitePawn, "d4").to_move(Color::Black);
+
+        let board = builder.clone().en_passant("d3").build().unwrap();
+        assert_eq!(board.en_passant(), Some("d3".into()));
+
+        let board = builder.clone().en_passant("d6").build();
+        assert_eq!(board, Err(SetupError::EnPassant("d6".into())));
+
+        let board = builder.to_move(Color::White).en_passant("d3").build();
+        assert_eq!(board, Err(SetupError::EnPassant("d3".into())));
+    }
+}
@@ -0,0 +1,195 @@
+use board::{Board, Piece, PieceKind};
+use movement::Move;
+use position::Position;
+
+/// What kind of move a `Move` is in the position it was generated in.
+/// Whether it gives check is left to `Board::gives_check`, as that means
+/// playing the move.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+pub struct MoveFlags {
+    pub capture: bool,
+    pub en_passant: bool,
+    pub promotion: bool,
+    pub castle: bool,
+}
+
+impl MoveFlags {
+    /// Neither a capture nor a promotion. Quiet moves may still give check.
+    pub fn is_quiet(&self) -> bool {
+        !self.capture && !self.promotion
+    }
+}
+
+impl Board {
+    /// The flags of `m` in this position, as the generators set them.
+    pub fn move_flags(&self, m: &Move) -> MoveFlags {
+        MoveFlags {
+            capture: self.is_capture(m),
+            en_passant: self.is_en_passant(m),
+            promotion: m.promotion.is_some(),
+            castle: m.is_castle(),
+        }
+    }
+
+    pub fn is_capture(&self, m: &Move) -> bool {
+        self.piece_at(m.end) != Piece::Empty || self.is_en_passant(m)
+    }
+
+    pub fn is_en_passant(&self, m: &Move) -> bool {
+        m.piece.kind() == Some(PieceKind::Pawn)
+            && m.start.column != m.end.column
+            && self.piece_at(m.end) == Piece::Empty
+    }
+
+    /// Whether playing `m` leaves the opponent in check.
+    pub fn gives_check(&self, m: &Move) -> bool {
+        let mut board = self.clone();
+        board.make_move(*m);
+        board.in_check(board.to_move)
+    }
+
+    /// The legal captures and promotions, as searched by quiescence. Only
+    /// these are checked for legality, so this is much cheaper than
+    /// picking them out of `legal_moves`.
+    pub fn captures_and_promotions(&self) -> Vec<Move> {
+        self.legal_moves_where(|start, end| self.is_noisy(start, end))
+    }
+
+    /// The legal moves that neither capture nor promote, castling included.
+    pub fn quiet_moves(&self) -> Vec<Move> {
+        self.legal_moves_where(|start, end| !self.is_noisy(start, end))
+    }
+
+    // Whether moving the piece on `start` to `end` captures or promotes,
+    // without building the move. Pawns only move sideways to capture.
+    fn is_noisy(&self, start: Position, end: Position) -> bool {
+        let pawn = self.piece_at(start).kind() == Some(PieceKind::Pawn);
+        self.piece_at(end) != Piece::Empty
+            || (pawn && (start.column != end.column || end.row == 1 || end.row == 8))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags() {
+        let mut board = Board::default();
+        for san in &["e4", "d5", "Nf3", "Nc6", "Bc4", "Qd6", "e5", "f5"] {
+            let m = board.parse_san_move(san).unwrap();
+            board.make_move(m);
+        }
+
+        let flags = |san: &str| board.parse_san_move(san).unwrap().flags;
+
+        assert_eq!(flags("Nd4"), MoveFlags::default());
+        assert_eq!(
+            flags("exd6"),
+            MoveFlags {
+                capture: true,
+                ..MoveFlags::default()
+            }
+        );
+        assert_eq!(
+            flags("exf6"),
+            MoveFlags {
+                capture: true,
+                en_passant: true,
+                ..MoveFlags::default()
+            }
+        );
+        assert_eq!(
+            flags("O-O"),
+            MoveFlags {
+                castle: true,
+                ..MoveFlags::default()
+            }
+        );
+        assert_eq!(
+            flags("Bxd5"),
+            MoveFlags {
+                capture: true,
+                ..MoveFlags::default()
+            }
+        );
+        assert!(flags("Bb5").is_quiet());
+
+        let bare = Move::new("e5".into(), "d6".into(), Piece::WhitePawn);
+        assert_eq!(bare.flags, MoveFlags::default());
+        assert_eq!(bare, board.parse_san_move("exd6").unwrap());
+        assert_eq!(board.move_flags(&bare), flags("exd6"));
+    }
+
+    #[test]
+    fn checks() {
+        let board = board!(
+            "
+            . . . . . k . .
+            . . . . . . . P
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . K . . R
+            "
+        );
+
+        let gives_check = |san: &str| board.gives_check(&board.parse_san_move(san).unwrap());
+        assert!(gives_check("O-O"));
+        assert!(gives_check("h8=Q"));
+        assert!(!gives_check("h8=N"));
+        assert!(!gives_check("Rh6"));
+
+        let rook = board.parse_san_move("h8=R").unwrap();
+        assert!(rook.flags.promotion && !rook.flags.is_quiet());
+        assert!(board.gives_check(&rook));
+    }
+
+    #[test]
+    fn move_generators() {
+        let board = board!(
+            "
+            . . . . k . . .
+            . . . . . . . P
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . K . . R
+            "
+        );
+
+        let noisy = board.captures_and_promotions();
+        let quiet = board.quiet_moves();
+        assert_eq!(noisy.len(), 4);
+        assert_eq!(noisy.len() + quiet.len(), board.legal_moves().len());
+        assert!(quiet.iter().any(|m| m.is_castle()));
+
+        let mut board = Board::default();
+        board.move_piece("e2", "e4");
+        board.move_piece("d7", "d5");
+        let captures = board.captures_and_promotions();
+        assert_eq!(captures.len(), 1);
+        assert_eq!(board.move_to_san(&captures[0]), "exd5");
+        assert_eq!(board.quiet_moves().len(), board.legal_moves().len() - 1);
+
+        // The pinned bishop can't take.
+        let board = board!(
+            "
+            . . . . r . k .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . p . . . .
+            . . . . B . . .
+            . . . . K . . .
+            "
+        );
+        assert!(board.captures_and_promotions().is_empty());
+        assert_eq!(board.quiet_moves().len(), board.legal_moves().len());
+    }
+}
@@ -0,0 +1,392 @@
+use std::fmt;
+
+use board::{Board, CastlingRights, Color, Piece};
+use position::{Column, Position};
+
+/// A printable 8x8 picture of a board. `Board`'s own `Display` draws one
+/// from white's side in ASCII, or with Unicode pieces for `{:#}`.
+pub struct Diagram<'a> {
+    board: &'a Board,
+    unicode: bool,
+    side: Color,
+}
+
+impl<'a> Diagram<'a> {
+    /// Use Unicode chess symbols rather than FEN letters.
+    pub fn unicode(mut self, unicode: bool) -> Diagram<'a> {
+        self.unicode = unicode;
+        self
+    }
+
+    /// Draw the board as seen by `side`, so black's pieces are at the
+    /// bottom when viewing from black.
+    pub fn from_side(mut self, side: Color) -> Diagram<'a> {
+        self.side = side;
+        self
+    }
+}
+
+impl Board {
+    pub fn diagram(&self) -> Diagram<'_> {
+        Diagram {
+            board: self,
+            unicode: false,
+            side: Color::White,
+        }
+    }
+}
+
+impl<'a> fmt::Display for Diagram<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut rows: Vec<u8> = (1..9).rev().collect();
+        let mut columns: Vec<Column> = (1..9).map(Column::from).collect();
+        if self.side == Color::Black {
+            rows.reverse();
+            columns.reverse();
+        }
+
+        for row in &rows {
+            write!(f, "{}", row)?;
+            for column in &columns {
+                let piece = self.board.piece_at(Position::new(*column, *row));
+                let c = if self.unicode {
+                    piece.glyph()
+                } else {
+                    piece.letter()
+                };
+                write!(f, " {}", c)?;
+            }
+            writeln!(f)?;
+        }
+
+        write!(f, " ")?;
+        for column in &columns {
+            write!(f, " {}", column)?;
+        }
+        writeln!(f)?;
+
+        let to_move = match self.board.to_move {
+            Color::White => "White",
+            Color::Black => "Black",
+        };
+
+        let rights = self.board.castling();
+        let mut castling = String::new();
+        for &(allowed, c) in &[
+            (rights.white_kingside, 'K'),
+            (rights.white_queenside, 'Q'),
+            (rights.black_kingside, 'k'),
+            (rights.black_queenside, 'q'),
+        ] {
+            if allowed {
+                castling.push(c);
+            }
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        let en_passant = match self.board.en_passant() {
+            Some(position) => position.to_string(),
+            None => "-".to_string(),
+        };
+
+        write!(
+            f,
+            "{} to move, castling {}, en passant {}",
+            to_move, castling, en_passant
+        )
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.diagram().unicode(f.alternate()))
+    }
+}
+
+impl Board {
+    /// Reads a diagram in the format `Display` writes. Rank and file labels
+    /// are optional, and the files (or failing that the ranks) decide which
+    /// side the board is seen from. Without a status line white is to move
+    /// and castling is allowed wherever a king and rook are at home.
+    pub fn from_diagram(diagram: &str) -> Result<Board, &'static str> {
+        let mut rows: Vec<(Option<u32>, Vec<Piece>)> = Vec::new();
+        let mut from_black = None;
+        let mut status = None;
+
+        for line in diagram.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.contains("to move") {
+                status = Some(line);
+                continue;
+            }
+
+            let chars: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+            if chars.len() == 8
+                && chars.contains(&'a')
+                && chars.iter().all(|c| ('a'..='h').contains(c))
+            {
+                from_black = Some(chars[0] == 'h');
+                continue;
+            }
+
+            let (label, squares) = match chars.first() {
+                Some(c) if chars.len() == 9 && ('1'..='8').contains(c) => {
+                    (c.to_digit(10), &chars[1..])
+                }
+                _ => (None, &chars[..]),
+            };
+
+            if squares.len() != 8 {
+                return Err("diagram rows must have 8 squares");
+            }
+
+            let pieces = squares
+                .iter()
+                .map(|c| Piece::from_char(*c))
+                .collect::<Option<Vec<Piece>>>()
+                .ok_or("unknown piece in diagram")?;
+            rows.push((label, pieces));
+        }
+
+        if rows.len() != 8 {
+            return Err("diagram must have 8 rows");
+        }
+
+        let from_black = from_black.unwrap_or(rows[0].0 == Some(1));
+        let mut board = Board::empty();
+
+        for (i, row) in rows.iter().enumerate() {
+            for (j, piece) in row.1.iter().enumerate() {
+                let (row, column) = if from_black {
+                    (i + 1, 8 - j)
+                } else {
+                    (8 - i, j + 1)
+                };
+                board.add_piece(*piece, Position::new(Column::from(column as i8), row as u8));
+            }
+        }
+
+        match status {
+            Some(line) => read_status(&mut board, line)?,
+            None => {
+                let white = board.piece_at("e1") == Piece::WhiteKing;
+                let black = board.piece_at("e8") == Piece::BlackKing;
+                let castling = CastlingRights {
+                    white_kingside: white && board.piece_at("h1") == Piece::WhiteRook,
+                    white_queenside: white && board.piece_at("a1") == Piece::WhiteRook,
+                    black_kingside: black && board.piece_at("h8") == Piece::BlackRook,
+                    black_queenside: black && board.piece_at("a8") == Piece::BlackRook,
+                };
+                board.set_castling(castling);
+            }
+        }
+
+        Ok(board)
+    }
+}
+
+// Reads a line such as `Black to move, castling Kq, en passant e3`.
+fn read_status(board: &mut Board, line: &str) -> Result<(), &'static str> {
+    for part in line.split(',') {
+        let part = part.trim();
+
+        if part.starts_with("White to move") {
+            board.to_move = Color::White;
+        } else if part.starts_with("Black to move") {
+            board.to_move = Color::Black;
+        } else if let Some(rights) = part.strip_prefix("castling") {
+            let mut castling = CastlingRights::none();
+            for c in rights.trim().chars() {
+                match c {
+                    'K' => castling.white_kingside = true,
+                    'Q' => castling.white_queenside = true,
+                    'k' => castling.black_kingside = true,
+                    'q' => castling.black_queenside = true,
+                    '-' => {}
+                    _ => return Err("invalid castling rights in diagram"),
+                }
+            }
+            board.set_castling(castling);
+        } else if let Some(square) = part.strip_prefix("en passant") {
+            let square = square.trim();
+            let en_passant = match square {
+                "-" => None,
+                _ => Some(square.parse()?),
+            };
+            board.set_en_passant(en_passant);
+        } else {
+            return Err("invalid status line in diagram");
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii() {
+        let board = Board::default();
+
+        assert_eq!(
+            board.to_string(),
+            "8 r n b q k b n r\n\
+             7 p p p p p p p p\n\
+             6 . . . . . . . .\n\
+             5 . . . . . . . .\n\
+             4 . . . . . . . .\n\
+             3 . . . . . . . .\n\
+             2 P P P P P P P P\n\
+             1 R N B Q K B N R\n\
+             \x20 a b c d e f g h\n\
+             White to move, castling KQkq, en passant -"
+        );
+    }
+
+    #[test]
+    fn unicode() {
+        let mut board = Board::empty();
+        board.add_piece(Piece::WhiteKing, "a1");
+        board.add_piece(Piece::BlackQueen, "h8");
+
+        let diagram = format!("{:#}", board);
+        let lines: Vec<&str> = diagram.lines().collect();
+        assert_eq!(lines[0], "8 . . . . . . . \u{265B}");
+        assert_eq!(lines[7], "1 \u{2654} . . . . . . .");
+        assert_eq!(board.diagram().unicode(true).to_string(), diagram);
+    }
+
+    #[test]
+    fn from_black() {
+        let mut board = Board::default();
+        board.move_piece("e2", "e4");
+
+        let diagram = board.diagram().from_side(Color::Black).to_string();
+        let lines: Vec<&str> = diagram.lines().collect();
+        assert_eq!(lines[0], "1 R N B K Q B N R");
+        assert_eq!(lines[4], "5 . . . . . . . .");
+        assert_eq!(lines[3], "4 . . . P . . . .");
+        assert_eq!(lines[7], "8 r n b k q b n r");
+        assert_eq!(lines[8], "  h g f e d c b a");
+        assert_eq!(lines[9], "Black to move, castling KQkq, en passant e3");
+    }
+
+    #[test]
+    fn status_line() {
+        let mut board = Board::default();
+        for uci in &["e2e4", "e7e5", "e1e2", "a7a5", "h2h3", "a8a6"] {
+            let m = board.parse_uci_move(uci).unwrap();
+            board.make_move(m);
+        }
+
+        assert!(board
+            .to_string()
+            .ends_with("White to move, castling k, en passant -"));
+
+        let board = Board::empty();
+        assert!(board
+            .to_string()
+            .ends_with("White to move, castling KQkq, en passant -"));
+    }
+
+    #[test]
+    fn read_diagram() {
+        let mut board = Board::default();
+        board.move_piece("d2", "d4");
+
+        let diagram = board.to_string();
+        let unicode = format!("{:#}", board);
+        let from_black = board.diagram().from_side(Color::Black).to_string();
+
+        for text in &[&diagram, &unicode, &from_black] {
+            let read = Board::from_diagram(text).unwrap();
+            assert_eq!(read.to_string(), diagram);
+            assert!(read.history().is_empty());
+        }
+    }
+
+    #[test]
+    fn read_bare_diagram() {
+        let board = Board::from_diagram(
+            "
+            . . . . k . . r
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            R . . . K . . .
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(board.piece_at("a1"), Piece::WhiteRook);
+        assert_eq!(board.piece_at("h8"), Piece::BlackRook);
+        assert_eq!(board.to_move, Color::White);
+        assert_eq!(
+            board.castling(),
+            CastlingRights {
+                white_kingside: false,
+                white_queenside: true,
+                black_kingside: true,
+                black_queenside: false,
+            }
+        );
+
+        let compact = Board::from_diagram(
+            "....k..r\n........\n........\n........\n........\n........\n........\nR...K...",
+        );
+        assert_eq!(compact, Ok(board));
+    }
+
+    #[test]
+    fn read_status_line() {
+        let board = board!(
+            "
+            8 . . . . k . . .
+            7 . . . . . . . .
+            6 . . . . . . . .
+            5 . . . p P . . .
+            4 . . . . . . . .
+            3 . . . . . . . .
+            2 . . . . . . . .
+            1 . . . . K . . R
+              a b c d e f g h
+            White to move, castling -, en passant d6
+            "
+        );
+
+        assert_eq!(board.castling(), CastlingRights::none());
+        assert_eq!(board.en_passant(), Some("d6".into()));
+        assert_eq!(board.legal_moves().len(), 2 + 5 + 9);
+    }
+
+    #[test]
+    fn invalid_diagrams() {
+        assert!(Board::from_diagram("").is_err());
+        assert!(Board::from_diagram(". . . .").is_err());
+        assert!(Board::from_diagram(&"........\n".repeat(7)).is_err());
+        assert!(Board::from_diagram(&"...x....\n".repeat(8)).is_err());
+
+        let status = format!(
+            "{}White to move, castling X, en passant -",
+            "........\n".repeat(8)
+        );
+        assert!(Board::from_diagram(&status).is_err());
+
+        let status = format!(
+            "{}White to move, castling -, en passant z9",
+            "........\n".repeat(8)
+        );
+        assert!(Board::from_diagram(&status).is_err());
+    }
+}
@@ -0,0 +1,480 @@
+use board::{Board, Color, PieceKind};
+use eval::{kpk, psqt};
+use square::Square;
+
+/// Added to the score of endgames known to be won, so the search prefers
+/// them to anything the ordinary evaluation comes up with, while keeping
+/// well clear of mate scores.
+pub const KNOWN_WIN: i32 = 10_000;
+
+/// The scale factor that leaves the evaluation as it is.
+pub const NORMAL_SCALE: i32 = 64;
+
+const OPPOSITE_BISHOPS_SCALE: i32 = 32;
+const OPPOSITE_BISHOPS_LEVEL_SCALE: i32 = 16;
+
+/// What a recognizer makes of a position it knows about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// The score from White's point of view, in place of the evaluation.
+    Exact(i32),
+    /// The evaluation is worth this many 64ths of itself, so zero is a
+    /// draw and `NORMAL_SCALE` changes nothing.
+    Scale(i32),
+}
+
+impl Verdict {
+    /// The score from White's point of view, given what the ordinary
+    /// evaluation makes of the position.
+    pub fn apply(self, score: i32) -> i32 {
+        match self {
+            Verdict::Exact(exact) => exact,
+            Verdict::Scale(scale) => score * scale / NORMAL_SCALE,
+        }
+    }
+}
+
+type Recognizer = fn(&Board, Color) -> Option<Verdict>;
+
+// The strong side's pieces and the weak side's, as in material signatures.
+const RECOGNIZERS: [(&str, &str, Recognizer); 4] = [
+    ("KQ", "K", mating_net),
+    ("KR", "K", mating_net),
+    ("KBN", "K", bishop_and_knight),
+    ("KP", "K", king_and_pawn),
+];
+
+/// Looks the position up by its material signature and, if it is an
+/// endgame the ordinary evaluation gets wrong, says what to make of it.
+pub fn recognize(board: &Board) -> Option<Verdict> {
+    // One look over the board turns most positions away before the
+    // signature is built.
+    let (mut pieces, mut pawns) = (0, 0);
+    for (_, piece) in board.enumerate_pieces() {
+        match piece.kind() {
+            Some(PieceKind::Pawn) => pawns += 1,
+            Some(PieceKind::King) | None => {}
+            Some(_) => pieces += 1,
+        }
+    }
+    if !might_be_known(pieces, pawns) {
+        return None;
+    }
+
+    let signature = board.material_signature();
+    let mut sides = signature.split('v');
+    let white = sides.next()?;
+    let black = sides.next()?;
+
+    for &(strong, weak, color) in &[(white, black, Color::White), (black, white, Color::Black)] {
+        for &(pieces, against, recognizer) in &RECOGNIZERS {
+            if strong == pieces && weak == against {
+                return recognizer(board, color);
+            }
+        }
+
+        if strong.trim_end_matches('P') == "KB" && strong.len() > 2 && weak == "K" {
+            if let Some(verdict) = wrong_rook_pawn(board, color) {
+                return Some(verdict);
+            }
+        }
+    }
+
+    if white.trim_end_matches('P') == "KB" && black.trim_end_matches('P') == "KB" {
+        return opposite_bishops(board, white.len() as i32 - black.len() as i32);
+    }
+
+    None
+}
+
+// Whether a position with this many pieces besides kings and pawns, and
+// this many pawns, could be one `recognize` knows: one of `RECOGNIZERS`,
+// or a bishop ending with any number of pawns.
+fn might_be_known(pieces: usize, pawns: usize) -> bool {
+    (1..=2).contains(&pieces)
+        || RECOGNIZERS.iter().any(|&(strong, weak, _)| {
+            let letters = || strong.chars().chain(weak.chars());
+            letters().filter(|&c| c != 'K' && c != 'P').count() == pieces
+                && letters().filter(|&c| c == 'P').count() == pawns
+        })
+}
+
+// Lone king against a queen or a rook: drive the king to the edge and
+// bring the other one closer.
+fn mating_net(board: &Board, strong: Color) -> Option<Verdict> {
+    let (king, defender) = kings(board, strong)?;
+    let material: i32 = board
+        .pieces(strong)
+        .into_iter()
+        .filter_map(|(_, piece)| piece.kind())
+        .map(|kind| psqt::material(kind).eg)
+        .sum();
+
+    let score = KNOWN_WIN + material + push_to_edge(defender) + push_close(king, defender);
+    Some(Verdict::Exact(from_white(score, strong)))
+}
+
+// Bishop and knight can only mate in a corner the bishop covers, so the
+// defending king is driven towards one of those.
+fn bishop_and_knight(board: &Board, strong: Color) -> Option<Verdict> {
+    let (king, defender) = kings(board, strong)?;
+    let bishop = Square::from(*board.squares_of(PieceKind::Bishop, strong).first()?);
+    let corners = if bishop.color() == Square::A1.color() {
+        [Square::A1, Square::H8]
+    } else {
+        [Square::H1, Square::A8]
+    };
+    let corner = corners
+        .iter()
+        .map(|&corner| defender.manhattan_distance(corner) as i32)
+        .min()?;
+
+    let score = KNOWN_WIN
+        + psqt::material(PieceKind::Bishop).eg
+        + psqt::material(PieceKind::Knight).eg
+        + (14 - corner) * 20
+        + push_close(king, defender);
+    Some(Verdict::Exact(from_white(score, strong)))
+}
+
+// Exact, from the bitbase.
+fn king_and_pawn(board: &Board, strong: Color) -> Option<Verdict> {
+    let (king, defender) = kings(board, strong)?;
+    let pawn = Square::from(*board.squares_of(PieceKind::Pawn, strong).first()?);
+
+    // Seen from the strong side with the pawn on the queen's side, as the
+    // bitbase wants it.
+    let orient = |square: Square| {
+        let square = match strong {
+            Color::White => square,
+            Color::Black => square.flip_vertical(),
+        };
+        if pawn.index() % 8 >= 4 {
+            square.flip_horizontal()
+        } else {
+            square
+        }
+    };
+    let (king, pawn, defender) = (orient(king), orient(pawn), orient(defender));
+    if !(2..=7).contains(&pawn.rank()) {
+        return None;
+    }
+
+    if kpk::probe(
+        board.to_move == strong,
+        king.index(),
+        pawn.index(),
+        defender.index(),
+    ) {
+        let score = KNOWN_WIN + psqt::material(PieceKind::Pawn).eg + pawn.rank() as i32;
+        Some(Verdict::Exact(from_white(score, strong)))
+    } else {
+        Some(Verdict::Exact(0))
+    }
+}
+
+// Rook pawns with a bishop that doesn't cover the queening square can't
+// win once the defending king gets to the corner.
+fn wrong_rook_pawn(board: &Board, strong: Color) -> Option<Verdict> {
+    let pawns: Vec<Square> = board
+        .squares_of(PieceKind::Pawn, strong)
+        .into_iter()
+        .map(Square::from)
+        .collect();
+    let file = pawns.first()?.index() % 8;
+    if (file != 0 && file != 7) || pawns.iter().any(|pawn| pawn.index() % 8 != file) {
+        return None;
+    }
+
+    let bishop = Square::from(*board.squares_of(PieceKind::Bishop, strong).first()?);
+    let promotion = match strong {
+        Color::White => Square::new(file as u8, 7),
+        Color::Black => Square::new(file as u8, 0),
+    };
+    let (_, defender) = kings(board, strong)?;
+
+    if bishop.color() != promotion.color() && defender.distance(promotion) <= 1 {
+        Some(Verdict::Scale(0))
+    } else {
+        None
+    }
+}
+
+// Bishops on opposite colors and nothing but pawns besides tend to be
+// drawn, all the more so when the pawns are nearly level.
+fn opposite_bishops(board: &Board, pawn_difference: i32) -> Option<Verdict> {
+    let white = Square::from(*board.squares_of(PieceKind::Bishop, Color::White).first()?);
+    let black = Square::from(*board.squares_of(PieceKind::Bishop, Color::Black).first()?);
+    if white.color() == black.color() {
+        return None;
+    }
+
+    if pawn_difference.abs() <= 1 {
+        Some(Verdict::Scale(OPPOSITE_BISHOPS_LEVEL_SCALE))
+    } else {
+        Some(Verdict::Scale(OPPOSITE_BISHOPS_SCALE))
+    }
+}
+
+fn kings(board: &Board, strong: Color) -> Option<(Square, Square)> {
+    let king = board.king_square(strong)?;
+    let defender = board.king_square(strong.opposite())?;
+    Some((Square::from(king), Square::from(defender)))
+}
+
+fn from_white(score: i32, strong: Color) -> i32 {
+    match strong {
+        Color::White => score,
+        Color::Black => -score,
+    }
+}
+
+// Grows from the centre towards the edges and more so the corners.
+fn push_to_edge(square: Square) -> i32 {
+    let file = (square.index() % 8) as i32;
+    let rank = (square.index() / 8) as i32;
+    let files = (3 - file).max(file - 4);
+    let ranks = (3 - rank).max(rank - 4);
+    10 * (files * files + ranks * ranks)
+}
+
+fn push_close(king: Square, defender: Square) -> i32 {
+    (7 - king.distance(defender) as i32) * 20
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_endgames() {
+        assert_eq!(recognize(&Board::default()), None);
+        assert!(might_be_known(0, 1) && might_be_known(2, 5));
+        assert!(!might_be_known(0, 2) && !might_be_known(3, 0));
+
+        let bishops = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . B B K . . .
+            "
+        );
+        assert_eq!(recognize(&bishops), None);
+
+        let pawns = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . P P . . .
+            . . . . K . . .
+            "
+        );
+        assert_eq!(recognize(&pawns), None);
+    }
+
+    #[test]
+    fn mating_nets() {
+        let edge = board!(
+            "
+            . . . k . . . .
+            . . . . . . . .
+            . . . K . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . R
+            "
+        );
+        let centre = board!(
+            "
+            . . . . . . . .
+            . . . . . . . .
+            . . . K . . . .
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . R
+            "
+        );
+
+        let edge = match recognize(&edge) {
+            Some(Verdict::Exact(score)) => score,
+            verdict => panic!("{:?}", verdict),
+        };
+        let centre = match recognize(&centre) {
+            Some(Verdict::Exact(score)) => score,
+            verdict => panic!("{:?}", verdict),
+        };
+        assert!(centre > KNOWN_WIN);
+        assert!(edge > centre);
+
+        let queen = board!(
+            "
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . q . .
+            . . . . . . . K
+            "
+        );
+        match recognize(&queen) {
+            Some(Verdict::Exact(score)) => assert!(score < -KNOWN_WIN),
+            verdict => panic!("{:?}", verdict),
+        }
+    }
+
+    #[test]
+    fn bishop_and_knight_corners() {
+        // A dark-squared bishop mates in a1 or h8.
+        let right = board!(
+            "
+            . . . . . . . k
+            . . . . . . . .
+            . . . . . K . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . N . .
+            . . . . . . . .
+            . . B . . . . .
+            "
+        );
+        let wrong = board!(
+            "
+            k . . . . . . .
+            . . . . . . . .
+            . . K . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . N . .
+            . . . . . . . .
+            . . B . . . . .
+            "
+        );
+
+        match (recognize(&right), recognize(&wrong)) {
+            (Some(Verdict::Exact(right)), Some(Verdict::Exact(wrong))) => {
+                assert!(wrong > KNOWN_WIN);
+                assert!(right > wrong);
+            }
+            verdicts => panic!("{:?}", verdicts),
+        }
+    }
+
+    #[test]
+    fn king_and_pawn() {
+        let mut board = board!(
+            "
+            . . . . . . . .
+            . . . k . . . .
+            . . . . . . . .
+            . . . K . . . .
+            . . . P . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            "
+        );
+        assert_eq!(recognize(&board), Some(Verdict::Exact(0)));
+
+        board.to_move = Color::Black;
+        match recognize(&board) {
+            Some(Verdict::Exact(score)) => assert!(score > KNOWN_WIN),
+            verdict => panic!("{:?}", verdict),
+        }
+
+        // The same for Black on the other wing.
+        let mut board = board!(
+            "
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . p . . .
+            . . . . k . . .
+            . . . . . . . .
+            . . . . K . . .
+            . . . . . . . .
+            "
+        );
+        board.to_move = Color::Black;
+        assert_eq!(recognize(&board), Some(Verdict::Exact(0)));
+        board.to_move = Color::White;
+        match recognize(&board) {
+            Some(Verdict::Exact(score)) => assert!(score < -KNOWN_WIN),
+            verdict => panic!("{:?}", verdict),
+        }
+    }
+
+    #[test]
+    fn scaling() {
+        // The light-squared bishop can't drive the king out of h8.
+        let wrong_bishop = board!(
+            "
+            . . . . . . . k
+            . . . . . . . .
+            . . . . . . . P
+            . . . . . . . P
+            . . . . . . . .
+            . . . B . . . .
+            . . . . . K . .
+            . . . . . . . .
+            "
+        );
+        assert_eq!(recognize(&wrong_bishop), Some(Verdict::Scale(0)));
+
+        let right_bishop = board!(
+            "
+            . . . . . . . k
+            . . . . . . . .
+            . . . . . . . P
+            . . . . . . . P
+            . . . . . . . .
+            . . . . B . . .
+            . . . . . K . .
+            . . . . . . . .
+            "
+        );
+        assert_eq!(recognize(&right_bishop), None);
+
+        let opposite = board!(
+            "
+            . . . . . . k .
+            . . . . . p . .
+            . . . . b . . .
+            . . . . . . . .
+            . . . P P . . .
+            . . . . . . . .
+            . . . . . B . .
+            . . . . . . K .
+            "
+        );
+        assert_eq!(recognize(&opposite), Some(Verdict::Scale(16)));
+
+        let same = board!(
+            "
+            . . . . . . k .
+            . . . . . p . .
+            . . . b . . . .
+            . . . . . . . .
+            . . . P P . . .
+            . . . . . . . .
+            . . . . . B . .
+            . . . . . . K .
+            "
+        );
+        assert_eq!(recognize(&same), None);
+    }
+}
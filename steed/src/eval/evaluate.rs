@@ -0,0 +1,82 @@
+use board::{Board, Color};
+use movement::Move;
+
+/// Anything the search can use to score positions. Scores are in
+/// centipawns from the point of view of the side to move.
+///
+/// The search tells the evaluator about each move it plays and takes back,
+/// so an evaluator that keeps state along the current line, such as the
+/// accumulator of a neural network, can update it bit by bit instead of
+/// starting over at every node. The hooks do nothing by default, which is
+/// all an evaluator that looks at each position afresh needs; any
+/// `FnMut(&Board) -> i32` works as one of those.
+pub trait Evaluate {
+    fn evaluate(&mut self, board: &Board) -> i32;
+
+    /// Called with the root position before each search.
+    fn reset(&mut self, _board: &Board) {}
+
+    /// Called before the search looks at the position after `m` is played
+    /// on `board`, or after a pass when `m` is `None`.
+    fn make_move(&mut self, _board: &Board, _m: Option<Move>) {}
+
+    /// Called when the search is done with the position after the most
+    /// recent `make_move` still in effect.
+    fn unmake_move(&mut self) {}
+}
+
+impl<F: FnMut(&Board) -> i32> Evaluate for F {
+    fn evaluate(&mut self, board: &Board) -> i32 {
+        self(board)
+    }
+}
+
+/// Counts material and nothing else, a pawn being worth 100.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Material;
+
+impl Evaluate for Material {
+    fn evaluate(&mut self, board: &Board) -> i32 {
+        let white = board.material(Color::White) as i32 - board.material(Color::Black) as i32;
+        match board.to_move {
+            Color::White => white * 100,
+            Color::Black => -white * 100,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stateless_evaluators() {
+        let board = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . Q K . . R
+            "
+        );
+        assert_eq!(Material.evaluate(&board), 1400);
+
+        let mut black = board.clone();
+        black.to_move = Color::Black;
+        assert_eq!(Material.evaluate(&black), -1400);
+
+        let mut calls = 0;
+        let mut counting = |_: &Board| {
+            calls += 1;
+            42
+        };
+        counting.make_move(&board, None);
+        assert_eq!(counting.evaluate(&board), 42);
+        counting.unmake_move();
+        assert_eq!(calls, 1);
+    }
+}
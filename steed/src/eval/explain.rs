@@ -0,0 +1,248 @@
+use std::fmt;
+
+use board::{Board, Color};
+use eval::endgame::{recognize, Verdict, NORMAL_SCALE};
+use eval::pawns::PawnEntry;
+use eval::{phase, Evaluator, Score, MAX_PHASE};
+
+/// The parts the evaluation is made up of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Term {
+    Material,
+    Placement,
+    Pawns,
+    Mobility,
+    KingSafety,
+}
+
+impl Term {
+    pub const ALL: [Term; 5] = [
+        Term::Material,
+        Term::Placement,
+        Term::Pawns,
+        Term::Mobility,
+        Term::KingSafety,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Term::Material => "Material",
+            Term::Placement => "PSQT",
+            Term::Pawns => "Pawns",
+            Term::Mobility => "Mobility",
+            Term::KingSafety => "King safety",
+        }
+    }
+}
+
+/// One term for each side, and the difference between them tapered by
+/// the game phase, from White's point of view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TermScores {
+    pub term: Term,
+    pub white: Score,
+    pub black: Score,
+    pub total: i32,
+}
+
+/// The evaluation of a position broken down term by term. Displaying it
+/// prints a table, and `diff` shows what changed between two positions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    pub terms: Vec<TermScores>,
+    pub phase: i32,
+    /// What the endgame recognizers made of the position, if it is one
+    /// they know.
+    pub endgame: Option<Verdict>,
+    /// The whole evaluation from White's point of view. It is tapered in
+    /// one go, so the term totals may not add up to it exactly, and an
+    /// endgame verdict overrides or scales it.
+    pub total: i32,
+}
+
+/// Explains `board` with the default evaluation weights.
+pub fn explain(board: &Board) -> Explanation {
+    Evaluator::new().explain(board)
+}
+
+impl Evaluator {
+    /// Breaks down how this evaluator scores `board`.
+    pub fn explain(&self, board: &Board) -> Explanation {
+        let pawns = PawnEntry::new(board);
+        let white = self.terms(board, Color::White, &pawns);
+        let black = self.terms(board, Color::Black, &pawns);
+        let phase = phase(board);
+
+        let terms: Vec<TermScores> = Term::ALL
+            .iter()
+            .zip(white.iter().zip(black.iter()))
+            .map(|(&term, (&white, &black))| TermScores {
+                term,
+                white,
+                black,
+                total: (white - black).taper(phase),
+            })
+            .collect();
+
+        let score = terms
+            .iter()
+            .fold(Score::ZERO, |score, term| score + term.white - term.black);
+
+        let endgame = recognize(board);
+        let total = score.taper(phase);
+
+        Explanation {
+            terms,
+            phase,
+            endgame,
+            total: endgame.map_or(total, |verdict| verdict.apply(total)),
+        }
+    }
+}
+
+impl Explanation {
+    pub fn term(&self, term: Term) -> &TermScores {
+        self.terms
+            .iter()
+            .find(|scores| scores.term == term)
+            .unwrap()
+    }
+
+    /// How much each number went up from `before` to this one.
+    pub fn diff(&self, before: &Explanation) -> Explanation {
+        let terms = self
+            .terms
+            .iter()
+            .zip(before.terms.iter())
+            .map(|(after, before)| TermScores {
+                term: after.term,
+                white: after.white - before.white,
+                black: after.black - before.black,
+                total: after.total - before.total,
+            })
+            .collect();
+
+        Explanation {
+            terms,
+            phase: self.phase - before.phase,
+            endgame: self.endgame,
+            total: self.total - before.total,
+        }
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<12}{:>10}{:>10}{:>10}{:>10}{:>10}",
+            "Term", "White MG", "White EG", "Black MG", "Black EG", "Total"
+        )?;
+
+        let mut white = Score::ZERO;
+        let mut black = Score::ZERO;
+        for scores in &self.terms {
+            writeln!(
+                f,
+                "{:<12}{:>10}{:>10}{:>10}{:>10}{:>10}",
+                scores.term.name(),
+                scores.white.mg,
+                scores.white.eg,
+                scores.black.mg,
+                scores.black.eg,
+                scores.total
+            )?;
+            white += scores.white;
+            black += scores.black;
+        }
+
+        writeln!(
+            f,
+            "{:<12}{:>10}{:>10}{:>10}{:>10}{:>10}",
+            "Total", white.mg, white.eg, black.mg, black.eg, self.total
+        )?;
+        match self.endgame {
+            Some(Verdict::Exact(score)) => writeln!(f, "Known endgame, scored {}", score)?,
+            Some(Verdict::Scale(scale)) => {
+                writeln!(f, "Known endgame, scaled {}/{}", scale, NORMAL_SCALE)?
+            }
+            None => {}
+        }
+        write!(f, "Phase {}/{}", self.phase, MAX_PHASE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eval::Evaluate;
+
+    #[test]
+    fn matches_the_evaluation() {
+        let mut board = Board::default();
+        for san in &["e4", "d5", "exd5", "Nf6"] {
+            let m = board.parse_san_move(san).unwrap();
+            board.make_move(m);
+        }
+
+        let explanation = explain(&board);
+        assert_eq!(explanation.phase, MAX_PHASE);
+        assert_eq!(explanation.total, Evaluator::new().evaluate(&board));
+
+        let material = explanation.term(Term::Material);
+        assert_eq!(material.white.mg - material.black.mg, 82);
+        assert_eq!(material.total, 82);
+        assert_eq!(explanation.terms.len(), Term::ALL.len());
+    }
+
+    #[test]
+    fn diffs() {
+        let before = Board::default();
+        let mut after = before.clone();
+        after.make_move(after.parse_san_move("Nf3").unwrap());
+
+        let diff = explain(&after).diff(&explain(&before));
+        assert_eq!(diff.phase, 0);
+        assert_eq!(diff.term(Term::Material).total, 0);
+        assert_eq!(diff.term(Term::Placement).black, Score::ZERO);
+        assert!(diff.term(Term::Placement).white.mg > 0);
+        assert!(diff.term(Term::Mobility).white.mg > 0);
+        assert_eq!(diff.total, explain(&after).total - explain(&before).total);
+    }
+
+    #[test]
+    fn table() {
+        let table = explain(&Board::default()).to_string();
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert_eq!(lines.len(), Term::ALL.len() + 3);
+        assert!(lines[0].starts_with("Term"));
+        assert!(lines[0].ends_with("Total"));
+        assert!(lines[1].starts_with("Material"));
+        assert!(lines[1].ends_with(" 0"));
+        assert_eq!(lines.last(), Some(&"Phase 24/24"));
+    }
+
+    #[test]
+    fn endgames() {
+        let board = board!(
+            "
+            . . . . . . k .
+            . . . . . p . .
+            . . . . b . . .
+            . . . . . . . .
+            . . . P P . . .
+            . . . . . . . .
+            . . . . . B . .
+            . . . . . . K .
+            "
+        );
+
+        let explanation = explain(&board);
+        assert_eq!(explanation.endgame, Some(Verdict::Scale(16)));
+        assert_eq!(explanation.total, Evaluator::new().evaluate(&board));
+        assert!(explanation
+            .to_string()
+            .contains("Known endgame, scaled 16/64"));
+    }
+}
@@ -0,0 +1,186 @@
+use attacks::king_attacks;
+use board::{Board, Color, PieceKind};
+use eval::params::EvalParams;
+use eval::Score;
+use square::Square;
+
+/// How safe `color`'s king is: the pieces bearing down on the squares
+/// around it, and the pawns and files in front of it.
+pub fn king_safety(board: &Board, color: Color, params: &EvalParams) -> Score {
+    match board.king_square(color) {
+        Some(king) => {
+            let king = Square::from(king);
+            attack(board, king, color, params) + shelter(board, king, color, params)
+        }
+        None => Score::ZERO,
+    }
+}
+
+// Every enemy piece attacking the king or the squares next to it adds its
+// units, and the total is scaled up with the number of attackers.
+fn attack(board: &Board, king: Square, color: Color, params: &EvalParams) -> Score {
+    let mut zone = king_attacks(king);
+    zone.insert(king);
+
+    let mut attackers = 0;
+    let mut units = 0;
+    for (position, piece) in board.pieces(color.opposite()) {
+        let kind = match piece.kind() {
+            Some(PieceKind::Pawn) | Some(PieceKind::King) | None => continue,
+            Some(kind) => kind,
+        };
+
+        let attacks = board.attacks_from(Square::from(position));
+        if !attacks.intersection(zone).is_empty() {
+            attackers += 1;
+            units += params.king_attack_units[kind as usize];
+        }
+    }
+
+    let scale = params.king_attack_scale[attackers.min(7)];
+    Score::new(-units * scale / 100, 0)
+}
+
+// The pawns on the king's file and those either side: its own in front of
+// it shelter it, the enemy's coming up the board threaten to break in, and
+// files without its own pawns leave it open to the rooks.
+fn shelter(board: &Board, king: Square, color: Color, params: &EvalParams) -> Score {
+    let ours = pawn_ranks(board, color, color);
+    let theirs = pawn_ranks(board, color.opposite(), color);
+    let king_rank = relative_rank(king, color);
+    let centre = (king.index() % 8).clamp(1, 6);
+
+    let mut score = Score::ZERO;
+    for file in centre - 1..=centre + 1 {
+        let nearest = |ranks: &[Vec<i32>]| {
+            ranks[file]
+                .iter()
+                .filter(|&&rank| rank > king_rank)
+                .map(|&rank| rank - king_rank)
+                .min()
+        };
+
+        score += match nearest(&ours) {
+            Some(1) => params.pawn_shield[0],
+            Some(2) => params.pawn_shield[1],
+            _ => params.pawn_shield[2],
+        };
+
+        if let Some(distance) = nearest(&theirs) {
+            score += params.pawn_storm[distance.min(4) as usize - 1];
+        }
+
+        if ours[file].is_empty() {
+            score += if theirs[file].is_empty() {
+                params.king_open_file
+            } else {
+                params.king_half_open_file
+            };
+        }
+    }
+    score
+}
+
+// The ranks of `color`'s pawns on each file, counted from `side`'s end of
+// the board.
+fn pawn_ranks(board: &Board, color: Color, side: Color) -> Vec<Vec<i32>> {
+    let mut files = vec![Vec::new(); 8];
+    for position in board.squares_of(PieceKind::Pawn, color) {
+        let square = Square::from(position);
+        files[square.index() % 8].push(relative_rank(square, side));
+    }
+    files
+}
+
+fn relative_rank(square: Square, color: Color) -> i32 {
+    match color {
+        Color::White => square.rank() as i32,
+        Color::Black => 9 - square.rank() as i32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pawn_shelter() {
+        let params = EvalParams::default();
+        let board = board!(
+            "
+            . . . . . . k .
+            . . . . . p . p
+            . . . . . . p .
+            . . . . . . . P
+            . . . . . . . .
+            . . . . . . . .
+            P P P . . . . .
+            . K . . . . . .
+            "
+        );
+
+        assert_eq!(
+            king_safety(&board, Color::White, &params),
+            params.pawn_shield[0] * 3
+        );
+        assert_eq!(
+            king_safety(&board, Color::Black, &params),
+            params.pawn_shield[0] * 2 + params.pawn_shield[1] + params.pawn_storm[2]
+        );
+
+        let open = board!(
+            "
+            . . . . . . k .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . P
+            . . . . . . . K
+            "
+        );
+        assert_eq!(
+            king_safety(&open, Color::White, &params),
+            params.pawn_shield[0] + params.pawn_shield[2] * 2 + params.king_open_file * 2
+        );
+        assert_eq!(
+            king_safety(&open, Color::Black, &params),
+            params.pawn_shield[2] * 3
+                + params.king_open_file * 2
+                + params.king_half_open_file
+                + params.pawn_storm[3]
+        );
+    }
+
+    #[test]
+    fn king_attackers() {
+        let params = EvalParams::default();
+        let board = board!(
+            "
+            . . . . . r k .
+            . . . . . p p p
+            . . . . . . . .
+            . . . . . . . Q
+            . . . . . . N .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . K .
+            "
+        );
+        let shelter = params.pawn_shield[0] * 3;
+
+        // The queen eyes h7 and the knight can't reach the king's zone yet.
+        assert_eq!(king_safety(&board, Color::Black, &params), shelter);
+
+        let mut board = board;
+        board.make_move(board.parse_san_move("Nf6+").unwrap());
+        let units = params.king_attack_units[PieceKind::Queen as usize]
+            + params.king_attack_units[PieceKind::Knight as usize];
+        let scale = params.king_attack_scale[2];
+        assert_eq!(
+            king_safety(&board, Color::Black, &params),
+            Score::new(-units * scale / 100, 0) + shelter
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use std::sync::OnceLock;
+
+// Every king and pawn against king position, with White holding the pawn
+// on one of the files a to d and ranks 2 to 7. Squares count from a1 = 0.
+const POSITIONS: usize = 2 * 24 * 64 * 64;
+
+const INVALID: u8 = 0;
+const UNKNOWN: u8 = 1;
+const DRAW: u8 = 2;
+const WIN: u8 = 4;
+
+static WINS: OnceLock<Vec<bool>> = OnceLock::new();
+
+/// Whether White wins with its king on `king` and a pawn on `pawn` against
+/// the black king on `defender`, given who is to move. The pawn has to be
+/// on one of the files a to d and ranks 2 to 7; mirror the board first if
+/// it isn't. The table is worked out the first time it is needed.
+pub fn probe(white_to_move: bool, king: usize, pawn: usize, defender: usize) -> bool {
+    WINS.get_or_init(generate)[index(white_to_move, king, pawn, defender)]
+}
+
+fn index(white_to_move: bool, king: usize, pawn: usize, defender: usize) -> usize {
+    let side = if white_to_move { 0 } else { 1 };
+    side | defender << 1 | king << 7 | (pawn % 8) << 13 | (6 - pawn / 8) << 15
+}
+
+// Goes over the table again and again, settling positions from those one
+// move on, until nothing changes. What is still unknown then is a draw.
+fn generate() -> Vec<bool> {
+    let mut results: Vec<u8> = (0..POSITIONS).map(initial).collect();
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for i in 0..POSITIONS {
+            if results[i] == UNKNOWN {
+                let result = classify(&results, i);
+                if result != UNKNOWN {
+                    results[i] = result;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    results.into_iter().map(|result| result == WIN).collect()
+}
+
+fn decode(i: usize) -> (bool, usize, usize, usize) {
+    let white_to_move = i & 1 == 0;
+    let defender = (i >> 1) & 63;
+    let king = (i >> 7) & 63;
+    let pawn = (6 - (i >> 15)) * 8 + ((i >> 13) & 3);
+    (white_to_move, king, pawn, defender)
+}
+
+fn initial(i: usize) -> u8 {
+    let (white_to_move, king, pawn, defender) = decode(i);
+    let promotion = pawn + 8;
+
+    if distance(king, defender) <= 1
+        || king == pawn
+        || defender == pawn
+        || (white_to_move && pawn_attacks(pawn, defender))
+    {
+        INVALID
+    } else if white_to_move
+        && pawn / 8 == 6
+        && king != promotion
+        && (distance(defender, promotion) > 1 || distance(king, promotion) == 1)
+    {
+        // The pawn queens and can't be taken.
+        WIN
+    } else if !white_to_move
+        && (neighbours(defender)
+            .all(|square| distance(square, king) <= 1 || pawn_attacks(pawn, square))
+            || (distance(defender, pawn) == 1 && distance(king, pawn) > 1))
+    {
+        // Stalemate, or the pawn is lost.
+        DRAW
+    } else {
+        UNKNOWN
+    }
+}
+
+fn classify(results: &[u8], i: usize) -> u8 {
+    let (white_to_move, king, pawn, defender) = decode(i);
+    let mut reachable = INVALID;
+
+    if white_to_move {
+        for square in neighbours(king) {
+            reachable |= results[index(false, square, pawn, defender)];
+        }
+        if pawn / 8 < 6 {
+            reachable |= results[index(false, king, pawn + 8, defender)];
+        }
+        if pawn / 8 == 1 && pawn + 8 != king && pawn + 8 != defender {
+            reachable |= results[index(false, king, pawn + 16, defender)];
+        }
+
+        if reachable & WIN != 0 {
+            WIN
+        } else if reachable & UNKNOWN != 0 {
+            UNKNOWN
+        } else {
+            DRAW
+        }
+    } else {
+        for square in neighbours(defender) {
+            reachable |= results[index(true, king, pawn, square)];
+        }
+
+        if reachable & DRAW != 0 {
+            DRAW
+        } else if reachable & UNKNOWN != 0 {
+            UNKNOWN
+        } else {
+            WIN
+        }
+    }
+}
+
+fn distance(a: usize, b: usize) -> usize {
+    let files = (a % 8) as i32 - (b % 8) as i32;
+    let ranks = (a / 8) as i32 - (b / 8) as i32;
+    files.abs().max(ranks.abs()) as usize
+}
+
+fn neighbours(square: usize) -> impl Iterator<Item = usize> {
+    (0..64).filter(move |&other| distance(square, other) == 1)
+}
+
+fn pawn_attacks(pawn: usize, square: usize) -> bool {
+    square / 8 == pawn / 8 + 1 && distance(pawn, square) == 1 && square % 8 != pawn % 8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use square::Square;
+
+    fn at(name: &str) -> usize {
+        name.parse::<Square>().unwrap().index()
+    }
+
+    #[test]
+    fn known_positions() {
+        // The defender can't catch the pawn.
+        assert!(probe(true, at("h1"), at("a5"), at("h8")));
+        assert!(!probe(false, at("h1"), at("a5"), at("b6")));
+
+        // Opposition in front of the pawn decides it.
+        assert!(probe(false, at("d5"), at("d4"), at("d7")));
+        assert!(!probe(true, at("d5"), at("d4"), at("d7")));
+
+        // A rook pawn is drawn once the defender reaches the corner.
+        assert!(!probe(true, at("b6"), at("a5"), at("a8")));
+        assert!(!probe(true, at("c7"), at("a6"), at("a8")));
+    }
+}
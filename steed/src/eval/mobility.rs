@@ -0,0 +1,75 @@
+use attacks::pawn_attacks;
+use board::{Board, Color, PieceKind};
+use eval::params::EvalParams;
+use eval::Score;
+use square::{Square, SquareSet};
+
+/// The mobility of `color`'s knights, bishops, rooks and queens, counting
+/// the squares each attacks that aren't held by its own side or covered by
+/// an enemy pawn.
+pub fn mobility(board: &Board, color: Color, params: &EvalParams) -> Score {
+    let covered = board
+        .squares_of(PieceKind::Pawn, color.opposite())
+        .into_iter()
+        .fold(SquareSet::new(), |covered, position| {
+            covered.union(pawn_attacks(Square::from(position), color.opposite()))
+        });
+    let unsafe_squares = covered.union(board.occupied_by(color));
+
+    let mut score = Score::ZERO;
+    for (position, piece) in board.pieces(color) {
+        let kind = match piece.kind() {
+            Some(PieceKind::Pawn) | Some(PieceKind::King) | None => continue,
+            Some(kind) => kind,
+        };
+
+        let squares = board
+            .attacks_from(Square::from(position))
+            .iter()
+            .filter(|&square| !unsafe_squares.contains(square))
+            .count() as i32;
+        score += params.mobility[kind as usize] * (squares - params.mobility_base[kind as usize]);
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safe_squares() {
+        let params = EvalParams::default();
+        let board = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . p . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            N . . . K . . .
+            "
+        );
+
+        // The pawn covers b3, leaving the knight only c2.
+        let knight = params.mobility[PieceKind::Knight as usize];
+        assert_eq!(mobility(&board, Color::White, &params), knight * (1 - 4));
+        assert_eq!(mobility(&board, Color::Black, &params), Score::ZERO);
+
+        let open = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . N . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . K . . .
+            "
+        );
+        assert_eq!(mobility(&open, Color::White, &params), knight * (8 - 4));
+    }
+}
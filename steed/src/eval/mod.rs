@@ -0,0 +1,344 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use board::{Board, Color, PieceKind};
+use square::Square;
+
+use self::endgame::recognize;
+use self::king::king_safety;
+use self::mobility::mobility;
+use self::params::EvalParams;
+use self::pawns::{PawnEntry, PawnTable};
+
+pub use self::evaluate::{Evaluate, Material};
+pub use self::explain::{explain, Explanation, Term, TermScores};
+
+pub mod endgame;
+mod evaluate;
+mod explain;
+pub mod king;
+mod kpk;
+pub mod mobility;
+pub mod params;
+pub mod pawns;
+pub mod psqt;
+
+/// The game phase with all the pieces on the board. Knights and bishops
+/// count one each, rooks two and queens four; pawns and kings don't count.
+pub const MAX_PHASE: i32 = 24;
+
+/// A pair of centipawn values, one for the middlegame and one for the
+/// endgame, which are blended by `taper` once the phase is known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Score {
+    pub mg: i32,
+    pub eg: i32,
+}
+
+impl Score {
+    pub const ZERO: Score = Score { mg: 0, eg: 0 };
+
+    pub const fn new(mg: i32, eg: i32) -> Score {
+        Score { mg, eg }
+    }
+
+    /// The middlegame value at `MAX_PHASE`, the endgame value at zero and
+    /// in proportion in between.
+    pub fn taper(self, phase: i32) -> i32 {
+        let phase = phase.clamp(0, MAX_PHASE);
+        (self.mg * phase + self.eg * (MAX_PHASE - phase)) / MAX_PHASE
+    }
+}
+
+impl Add for Score {
+    type Output = Score;
+
+    fn add(self, other: Score) -> Score {
+        Score::new(self.mg + other.mg, self.eg + other.eg)
+    }
+}
+
+impl Sub for Score {
+    type Output = Score;
+
+    fn sub(self, other: Score) -> Score {
+        Score::new(self.mg - other.mg, self.eg - other.eg)
+    }
+}
+
+impl Neg for Score {
+    type Output = Score;
+
+    fn neg(self) -> Score {
+        Score::new(-self.mg, -self.eg)
+    }
+}
+
+impl Mul<i32> for Score {
+    type Output = Score;
+
+    fn mul(self, factor: i32) -> Score {
+        Score::new(self.mg * factor, self.eg * factor)
+    }
+}
+
+impl AddAssign for Score {
+    fn add_assign(&mut self, other: Score) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Score {
+    fn sub_assign(&mut self, other: Score) {
+        *self = *self - other;
+    }
+}
+
+/// How much of the middlegame is left, from `MAX_PHASE` with every piece
+/// on the board down to zero with only kings and pawns. Promotions can
+/// push the count past the maximum, so it is capped.
+pub fn phase(board: &Board) -> i32 {
+    let phase: i32 = board
+        .enumerate_pieces()
+        .filter_map(|(_, piece)| piece.kind())
+        .map(|kind| match kind {
+            PieceKind::Knight | PieceKind::Bishop => 1,
+            PieceKind::Rook => 2,
+            PieceKind::Queen => 4,
+            PieceKind::Pawn | PieceKind::King => 0,
+        })
+        .sum();
+
+    phase.min(MAX_PHASE)
+}
+
+/// Static evaluation of a position in centipawns, from the point of view of
+/// the side to move. Pawn structure is cached, so an evaluator should be
+/// kept around rather than made for each position.
+#[derive(Debug, Clone, Default)]
+pub struct Evaluator {
+    params: EvalParams,
+    pawns: PawnTable,
+}
+
+impl Evaluator {
+    pub fn new() -> Evaluator {
+        Evaluator::default()
+    }
+
+    pub fn with_params(params: EvalParams) -> Evaluator {
+        Evaluator {
+            params,
+            ..Evaluator::default()
+        }
+    }
+
+    pub fn params(&self) -> &EvalParams {
+        &self.params
+    }
+
+    /// Empties the pawn structure cache.
+    pub fn clear(&mut self) {
+        self.pawns.clear();
+    }
+
+    /// Material and piece-square values for `color`'s pieces.
+    pub fn material(&self, board: &Board, color: Color) -> Score {
+        let (material, placement) = material_and_placement(board, color);
+        material + placement
+    }
+
+    // Each term of the evaluation for one side, in the order of
+    // `Term::ALL`.
+    fn terms(&self, board: &Board, color: Color, pawns: &PawnEntry) -> [Score; 5] {
+        let (material, placement) = material_and_placement(board, color);
+        [
+            material,
+            placement,
+            pawns.structure[color as usize] + pawns.free_passers(board, color),
+            mobility(board, color, &self.params),
+            king_safety(board, color, &self.params),
+        ]
+    }
+}
+
+fn material_and_placement(board: &Board, color: Color) -> (Score, Score) {
+    let mut material = Score::ZERO;
+    let mut placement = Score::ZERO;
+    for (position, piece) in board.pieces(color) {
+        if let Some(kind) = piece.kind() {
+            material += psqt::material(kind);
+            placement += psqt::placement(kind, color, Square::from(position));
+        }
+    }
+    (material, placement)
+}
+
+impl Evaluate for Evaluator {
+    fn evaluate(&mut self, board: &Board) -> i32 {
+        let pawns = self.pawns.probe(board);
+        let white = self.terms(board, Color::White, &pawns);
+        let black = self.terms(board, Color::Black, &pawns);
+        let mut score = white
+            .iter()
+            .zip(black.iter())
+            .fold(Score::ZERO, |score, (&white, &black)| score + white - black)
+            .taper(phase(board));
+        if let Some(verdict) = recognize(board) {
+            score = verdict.apply(score);
+        }
+
+        match board.to_move {
+            Color::White => score,
+            Color::Black => -score,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores() {
+        let score = Score::new(100, -20) + Score::new(10, 30) * 2;
+        assert_eq!(score, Score::new(120, 40));
+        assert_eq!(-score - score, Score::new(-240, -80));
+
+        assert_eq!(score.taper(MAX_PHASE), 120);
+        assert_eq!(score.taper(0), 40);
+        assert_eq!(score.taper(MAX_PHASE / 2), 80);
+        assert_eq!(score.taper(100), 120);
+    }
+
+    #[test]
+    fn phases() {
+        assert_eq!(phase(&Board::default()), MAX_PHASE);
+
+        let board = board!(
+            "
+            . . . . k . . .
+            . . . . p . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . P . . .
+            . . . . K . . .
+            "
+        );
+        assert_eq!(phase(&board), 0);
+
+        let board = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . Q K . . R
+            "
+        );
+        assert_eq!(phase(&board), 6);
+    }
+
+    #[test]
+    fn start_position_is_level() {
+        let mut evaluator = Evaluator::new();
+        let mut board = Board::default();
+        assert_eq!(evaluator.evaluate(&board), 0);
+
+        board.make_move(board.parse_san_move("e4").unwrap());
+        let after = evaluator.evaluate(&board);
+        assert!(after < 0);
+        assert_eq!(evaluator.evaluate(&board.flip_colors()), after);
+    }
+
+    #[test]
+    fn material_and_placement() {
+        let mut evaluator = Evaluator::new();
+        let board = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . Q K . . .
+            "
+        );
+        let score = evaluator.evaluate(&board);
+        assert!(score > 900);
+        assert!(
+            evaluator.material(&board, Color::White).mg
+                > evaluator.material(&board, Color::Black).mg + 900
+        );
+
+        let mut black = board.clone();
+        black.to_move = Color::Black;
+        assert_eq!(evaluator.evaluate(&black), -score);
+
+        let centre = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . N . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . K . . .
+            "
+        );
+        let corner = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            N . . . K . . .
+            "
+        );
+        assert!(evaluator.evaluate(&centre) > evaluator.evaluate(&corner));
+    }
+
+    #[test]
+    fn pawn_structure() {
+        let mut evaluator = Evaluator::new();
+        let healthy = board!(
+            "
+            . . . . k . . .
+            . . . . . p p p
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . P P P
+            . . . . K . . .
+            "
+        );
+        let doubled = board!(
+            "
+            . . . . k . . .
+            . . . . . p p p
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . P .
+            . . . . . P P .
+            . . . . K . . .
+            "
+        );
+        assert_eq!(evaluator.evaluate(&healthy), 0);
+        assert!(evaluator.evaluate(&doubled) < 0);
+
+        evaluator.clear();
+        assert_eq!(evaluator.evaluate(&healthy), 0);
+    }
+}
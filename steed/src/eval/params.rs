@@ -0,0 +1,55 @@
+use eval::Score;
+
+/// The weights for the mobility and king safety terms, kept apart from the
+/// code so they can be tuned. Arrays indexed by piece are in `PieceKind`
+/// order: pawn, knight, bishop, rook, queen, king.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalParams {
+    /// The value of each safe square a piece can move to, counted from
+    /// `mobility_base`, so a piece with fewer squares than that loses.
+    pub mobility: [Score; 6],
+    pub mobility_base: [i32; 6],
+    /// How dangerous each kind of piece attacking the king's zone is.
+    pub king_attack_units: [i32; 6],
+    /// The share of the attack units, in percent, that counts against the
+    /// king for each number of attackers. One piece alone rarely gets
+    /// anywhere, so it should count for little.
+    pub king_attack_scale: [i32; 8],
+    /// For each file next to the king, a pawn of its own one and two ranks
+    /// in front, or none at all.
+    pub pawn_shield: [Score; 3],
+    /// For each file next to the king, an enemy pawn one, two, three or
+    /// four or more ranks away.
+    pub pawn_storm: [Score; 4],
+    /// A file next to the king without pawns of either side.
+    pub king_open_file: Score,
+    /// A file next to the king with only enemy pawns on it.
+    pub king_half_open_file: Score,
+}
+
+impl Default for EvalParams {
+    fn default() -> EvalParams {
+        EvalParams {
+            mobility: [
+                Score::new(0, 0),
+                Score::new(4, 4),
+                Score::new(5, 5),
+                Score::new(2, 4),
+                Score::new(1, 2),
+                Score::new(0, 0),
+            ],
+            mobility_base: [0, 4, 6, 7, 13, 0],
+            king_attack_units: [0, 20, 20, 40, 80, 0],
+            king_attack_scale: [0, 0, 50, 75, 88, 94, 97, 99],
+            pawn_shield: [Score::new(12, 0), Score::new(6, 0), Score::new(-14, 0)],
+            pawn_storm: [
+                Score::new(-4, 0),
+                Score::new(-18, 0),
+                Score::new(-8, 0),
+                Score::new(0, 0),
+            ],
+            king_open_file: Score::new(-25, 0),
+            king_half_open_file: Score::new(-12, 0),
+        }
+    }
+}
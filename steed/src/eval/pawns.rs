@@ -0,0 +1,390 @@
+use std::mem::size_of;
+
+use board::{Board, Color, PieceKind};
+use eval::Score;
+use square::{Square, SquareSet};
+
+const DOUBLED: Score = Score::new(-11, -25);
+const ISOLATED: Score = Score::new(-6, -14);
+const BACKWARD: Score = Score::new(-9, -18);
+
+// Indexed by rank counted from the pawn's own side, so a pawn on its
+// starting square is on rank 2 whichever colour it is.
+const CONNECTED: [Score; 8] = [
+    Score::new(0, 0),
+    Score::new(0, 0),
+    Score::new(4, 2),
+    Score::new(7, 4),
+    Score::new(12, 8),
+    Score::new(24, 18),
+    Score::new(45, 40),
+    Score::new(0, 0),
+];
+
+const PASSED: [Score; 8] = [
+    Score::new(0, 0),
+    Score::new(3, 8),
+    Score::new(6, 14),
+    Score::new(12, 28),
+    Score::new(30, 55),
+    Score::new(55, 100),
+    Score::new(90, 150),
+    Score::new(0, 0),
+];
+
+// On top of `PASSED` when nothing stands between the pawn and its
+// promotion square. Blockers other than pawns aren't part of the pawn
+// hash, so this is worked out fresh each time.
+const FREE_PASSED: [Score; 8] = [
+    Score::new(0, 0),
+    Score::new(0, 2),
+    Score::new(0, 5),
+    Score::new(3, 12),
+    Score::new(8, 25),
+    Score::new(15, 45),
+    Score::new(25, 70),
+    Score::new(0, 0),
+];
+
+/// The pawn structure of a position, which only depends on where the pawns
+/// are and so can be cached by `Board::pawn_hash`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PawnEntry {
+    pub key: u64,
+    /// Everything but the free path bonuses, for White and for Black.
+    pub structure: [Score; 2],
+    pub passed: [SquareSet; 2],
+}
+
+impl PawnEntry {
+    pub fn new(board: &Board) -> PawnEntry {
+        let pawns = [pawns(board, Color::White), pawns(board, Color::Black)];
+        let white = structure(Color::White, pawns[0], pawns[1]);
+        let black = structure(Color::Black, pawns[1], pawns[0]);
+
+        PawnEntry {
+            key: board.pawn_hash(),
+            structure: [white.0, black.0],
+            passed: [white.1, black.1],
+        }
+    }
+
+    /// The structure from White's point of view.
+    pub fn score(&self) -> Score {
+        self.structure[0] - self.structure[1]
+    }
+
+    /// The bonus for `color`'s passed pawns that have a clear run to
+    /// promotion.
+    pub fn free_passers(&self, board: &Board, color: Color) -> Score {
+        let mut score = Score::ZERO;
+        for square in self.passed[color as usize] {
+            let path = square.ray(0, forward(color));
+            if path
+                .iter()
+                .all(|square| board.piece_at(square).kind().is_none())
+            {
+                score += FREE_PASSED[relative_rank(square, color)];
+            }
+        }
+        score
+    }
+}
+
+/// A cache of pawn structure evaluations keyed by `Board::pawn_hash`.
+/// Pawn structures change much less often than positions do, so most
+/// lookups are hits.
+#[derive(Debug, Clone)]
+pub struct PawnTable {
+    entries: Vec<Option<PawnEntry>>,
+}
+
+impl PawnTable {
+    /// A table taking up about `kilobytes` of memory, though always with
+    /// room for at least one entry.
+    pub fn new(kilobytes: usize) -> PawnTable {
+        let slots = (kilobytes * 1024 / size_of::<Option<PawnEntry>>()).max(1);
+        PawnTable {
+            entries: vec![None; slots],
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn clear(&mut self) {
+        for entry in &mut self.entries {
+            *entry = None;
+        }
+    }
+
+    /// The entry for `board`'s pawns, worked out and stored if it isn't
+    /// in the table already.
+    pub fn probe(&mut self, board: &Board) -> PawnEntry {
+        let key = board.pawn_hash();
+        let index = (key % self.entries.len() as u64) as usize;
+
+        match self.entries[index] {
+            Some(entry) if entry.key == key => entry,
+            _ => {
+                let entry = PawnEntry::new(board);
+                self.entries[index] = Some(entry);
+                entry
+            }
+        }
+    }
+}
+
+impl Default for PawnTable {
+    fn default() -> PawnTable {
+        PawnTable::new(256)
+    }
+}
+
+fn pawns(board: &Board, color: Color) -> SquareSet {
+    board
+        .squares_of(PieceKind::Pawn, color)
+        .into_iter()
+        .map(Square::from)
+        .collect()
+}
+
+fn forward(color: Color) -> i8 {
+    match color {
+        Color::White => 1,
+        Color::Black => -1,
+    }
+}
+
+fn relative_rank(square: Square, color: Color) -> usize {
+    match color {
+        Color::White => square.rank() as usize - 1,
+        Color::Black => 8 - square.rank() as usize,
+    }
+}
+
+// The squares on the same and neighbouring files, in front of `square`
+// when `ranks` is positive and behind it when negative.
+fn span(square: Square, ranks: i8) -> SquareSet {
+    [-1, 0, 1]
+        .iter()
+        .filter_map(|&files| square.offset(files, 0))
+        .fold(SquareSet::new(), |span, start| {
+            span.union(start.ray(0, ranks))
+        })
+}
+
+// The squares from which a pawn of `color` attacks `square`.
+fn attackers(square: Square, color: Color) -> SquareSet {
+    [-1, 1]
+        .iter()
+        .filter_map(|&files| square.offset(files, -forward(color)))
+        .collect()
+}
+
+fn file(square: Square) -> i8 {
+    (square.index() % 8) as i8
+}
+
+// The structure score for `color`, and which of its pawns are passed.
+fn structure(color: Color, ours: SquareSet, theirs: SquareSet) -> (Score, SquareSet) {
+    let mut score = Score::ZERO;
+    let mut passed = SquareSet::new();
+    let up = forward(color);
+
+    for square in ours {
+        let rank = relative_rank(square, color);
+        let neighbours: Vec<usize> = ours
+            .iter()
+            .filter(|&other| (file(other) - file(square)).abs() == 1)
+            .map(|other| relative_rank(other, color))
+            .collect();
+
+        // Only the rear pawn of a doubled pair is penalised, so each extra
+        // pawn on a file counts once.
+        let doubled = !square.ray(0, up).intersection(ours).is_empty();
+        if doubled {
+            score += DOUBLED;
+        }
+
+        if neighbours.is_empty() {
+            score += ISOLATED;
+        } else if neighbours
+            .iter()
+            .any(|&other| other == rank || other + 1 == rank)
+        {
+            score += CONNECTED[rank];
+        } else {
+            // With its neighbours all gone on ahead, nothing can come to
+            // its defence, and it can't step up without being taken.
+            let stopped = square.offset(0, up).is_some_and(|stop| {
+                !attackers(stop, color.opposite())
+                    .intersection(theirs)
+                    .is_empty()
+            });
+            if stopped && neighbours.iter().all(|&other| other > rank) {
+                score += BACKWARD;
+            }
+        }
+
+        if !doubled && span(square, up).intersection(theirs).is_empty() {
+            score += PASSED[rank];
+            passed.insert(square);
+        }
+    }
+
+    (score, passed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn structure_of(board: &Board, color: Color) -> Score {
+        let pawns = [pawns(board, Color::White), pawns(board, Color::Black)];
+        let (ours, theirs) = match color {
+            Color::White => (pawns[0], pawns[1]),
+            Color::Black => (pawns[1], pawns[0]),
+        };
+        structure(color, ours, theirs).0
+    }
+
+    #[test]
+    fn doubled_and_isolated() {
+        let board = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . P . . . . .
+            . . P . . . . .
+            . . . . . . . .
+            . . . . K . . .
+            "
+        );
+        assert_eq!(
+            structure_of(&board, Color::White),
+            DOUBLED + ISOLATED * 2 + PASSED[3]
+        );
+        assert_eq!(structure_of(&board, Color::Black), Score::ZERO);
+    }
+
+    #[test]
+    fn connected_pawns() {
+        let board = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . p . .
+            . . . . . . . .
+            . . . . P . . .
+            . . . P . . . .
+            . . . . . . . .
+            . . . . K . . .
+            "
+        );
+        // d3 defends e4, which f6 keeps from being passed.
+        assert_eq!(structure_of(&board, Color::White), CONNECTED[3] + PASSED[2]);
+        assert_eq!(structure_of(&board, Color::Black), ISOLATED);
+    }
+
+    #[test]
+    fn backward_pawns() {
+        let board = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . p . . .
+            . . P . . . . .
+            . . . P . . . .
+            . . . . . . . .
+            . . . . K . . .
+            "
+        );
+        assert_eq!(
+            structure_of(&board, Color::White),
+            BACKWARD + CONNECTED[3] + PASSED[3]
+        );
+
+        // Without e5 watching d4, d3 can still come up.
+        let board = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . P . . . . .
+            . . . P . . . .
+            . . . . . . . .
+            . . . . K . . .
+            "
+        );
+        assert_eq!(
+            structure_of(&board, Color::White),
+            CONNECTED[3] + PASSED[3] + PASSED[2]
+        );
+    }
+
+    #[test]
+    fn passed_pawns() {
+        let board = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . P . . . . . .
+            . . . . . . . .
+            . . . . . . p .
+            . . . . . . . .
+            . . . . . . P .
+            . . . . K . . .
+            "
+        );
+        let entry = PawnEntry::new(&board);
+        assert_eq!(entry.passed[0].len(), 1);
+        assert!(entry.passed[0].contains("b6".parse().unwrap()));
+        assert!(entry.passed[1].is_empty());
+        assert_eq!(entry.free_passers(&board, Color::White), FREE_PASSED[5]);
+
+        let blocked = board!(
+            "
+            . n . . k . . .
+            . . . . . . . .
+            . P . . . . . .
+            . . . . . . . .
+            . . . . . . p .
+            . . . . . . . .
+            . . . . . . P .
+            . . . . K . . .
+            "
+        );
+        assert_eq!(
+            PawnEntry::new(&blocked).free_passers(&blocked, Color::White),
+            Score::ZERO
+        );
+    }
+
+    #[test]
+    fn pawn_table() {
+        let mut table = PawnTable::new(1);
+        assert!(table.capacity() > 1);
+
+        let board = Board::default();
+        let entry = table.probe(&board);
+        assert_eq!(entry, PawnEntry::new(&board));
+        assert_eq!(entry.score(), Score::ZERO);
+        assert_eq!(table.probe(&board), entry);
+
+        let mut tiny = PawnTable::new(0);
+        assert_eq!(tiny.capacity(), 1);
+        let mut after = board.clone();
+        after.make_move(after.parse_san_move("e4").unwrap());
+        assert_eq!(tiny.probe(&after), PawnEntry::new(&after));
+        assert_eq!(tiny.probe(&board), entry);
+
+        table.clear();
+        assert_eq!(table.probe(&board), entry);
+    }
+}
@@ -1,3 +1,26 @@
+/// Builds a `Board` from a text diagram, panicking if it can't be read. See
+/// `Board::from_diagram` for the format.
+#[macro_export]
+macro_rules! board {
+    ($diagram:expr) => {
+        $crate::board::Board::from_diagram($diagram).unwrap()
+    };
+}
+
+pub mod attacks;
 pub mod board;
+pub mod builder;
+pub mod classify;
+pub mod diagram;
+pub mod eval;
 pub mod movement;
+pub mod pgn;
 pub mod position;
+pub mod san;
+pub mod search;
+pub mod see;
+pub mod square;
+pub mod transform;
+pub mod tree;
+pub mod uci;
+pub mod zobrist;
@@ -1,14 +1,160 @@
 use board::{Board, Color, Piece};
-use position::Position;
-
-#[derive(Debug, PartialEq, Copy, Clone)]
+use classify::MoveFlags;
+use position::{Column, Position};
+use square::Square;
+
+/// A move of `piece` from `start` to `end`. The flags are filled in by
+/// the move generators for the position the move was generated in; a move
+/// built by hand with `new` or `promote` has none set until it is given
+/// `Board::move_flags`. They take no part in comparing moves.
+#[derive(Debug, Copy, Clone)]
 pub struct Move {
-    start: Position,
-    end: Position,
-    piece: Piece,
+    pub start: Position,
+    pub end: Position,
+    pub piece: Piece,
+    pub promotion: Option<Piece>,
+    pub flags: MoveFlags,
+}
+
+impl PartialEq for Move {
+    fn eq(&self, other: &Move) -> bool {
+        self.start == other.start
+            && self.end == other.end
+            && self.piece == other.piece
+            && self.promotion == other.promotion
+    }
+}
+
+impl Move {
+    pub fn new(start: Position, end: Position, piece: Piece) -> Move {
+        Move {
+            start,
+            end,
+            piece,
+            promotion: None,
+            flags: MoveFlags::default(),
+        }
+    }
+
+    pub fn promote(start: Position, end: Position, piece: Piece, promotion: Piece) -> Move {
+        Move {
+            start,
+            end,
+            piece,
+            promotion: Some(promotion),
+            flags: MoveFlags::default(),
+        }
+    }
+
+    pub fn is_castle(&self) -> bool {
+        let start: i8 = self.start.column.into();
+        let end: i8 = self.end.column.into();
+
+        match self.piece {
+            Piece::WhiteKing | Piece::BlackKing => (end - start).abs() == 2,
+            _ => false,
+        }
+    }
 }
 
 impl Board {
+    pub fn legal_moves(&self) -> Vec<Move> {
+        self.legal_moves_where(|_, _| true)
+    }
+
+    /// The legal moves whose start and destination squares pass `wanted`.
+    /// It is asked before making sure the king isn't left in check, which
+    /// is the costly part, so moves left out this way cost next to nothing.
+    pub fn legal_moves_where<F: Fn(Position, Position) -> bool>(&self, wanted: F) -> Vec<Move> {
+        let mut moves = Vec::new();
+
+        for (i, piece) in self.enumerate_pieces() {
+            if *piece == Piece::Empty || piece.color() != self.to_move {
+                continue;
+            }
+
+            let start: Position = i.into();
+            let targets = self.filter_occupied_space(start, self.moves_for(start), piece.color());
+            let targets = targets
+                .into_iter()
+                .filter(|&end| wanted(start, end))
+                .collect();
+
+            for end in self.filter_in_check(start, targets, piece.color()) {
+                match *piece {
+                    Piece::WhitePawn | Piece::BlackPawn if end.row == 1 || end.row == 8 => {
+                        for promotion in Board::promotions(piece.color()) {
+                            moves.push(Move::promote(start, end, *piece, promotion));
+                        }
+                    }
+                    _ => moves.push(Move::new(start, end, *piece)),
+                }
+            }
+        }
+
+        for m in &mut moves {
+            m.flags = self.move_flags(m);
+        }
+
+        moves
+    }
+
+    /// Whether `m` can be played in this position, as when it was
+    /// remembered from another one. Only the destination of `m` is checked
+    /// for leaving the king in check.
+    pub fn is_legal(&self, m: &Move) -> bool {
+        let piece = self.piece_at(m.start);
+        if piece == Piece::Empty || piece != m.piece || piece.color() != self.to_move {
+            return false;
+        }
+
+        let promotes = match piece {
+            Piece::WhitePawn | Piece::BlackPawn => m.end.row == 1 || m.end.row == 8,
+            _ => false,
+        };
+        let promotion = match m.promotion {
+            Some(promotion) => promotes && Board::promotions(piece.color()).contains(&promotion),
+            None => !promotes,
+        };
+        if !promotion || !self.moves_for(m.start).contains(&m.end) {
+            return false;
+        }
+
+        let target = self.filter_occupied_space(m.start, vec![m.end], piece.color());
+        !self
+            .filter_in_check(m.start, target, piece.color())
+            .is_empty()
+    }
+
+    /// Whether the side to move has a legal move at all. Destinations are
+    /// checked one at a time and the first legal one ends the search, so
+    /// this is much cheaper than generating the moves.
+    pub fn has_legal_move(&self) -> bool {
+        let color = self.to_move;
+        self.pieces(color).into_iter().any(|(start, _)| {
+            self.filter_occupied_space(start, self.moves_for(start), color)
+                .into_iter()
+                .any(|end| !self.filter_in_check(start, vec![end], color).is_empty())
+        })
+    }
+
+    fn promotions(color: Color) -> Vec<Piece> {
+        match color {
+            Color::White => vec![
+                Piece::WhiteQueen,
+                Piece::WhiteRook,
+                Piece::WhiteBishop,
+                Piece::WhiteKnight,
+            ],
+            Color::Black => vec![
+                Piece::BlackQueen,
+                Piece::BlackRook,
+                Piece::BlackBishop,
+                Piece::BlackKnight,
+            ],
+        }
+    }
+
     pub fn filtered_moves_for<P: Into<Position>>(&self, position: P) -> Vec<Position> {
         let position = position.into();
         let piece = self.piece_at(position);
@@ -49,46 +195,52 @@ impl Board {
         column_offset: i8,
         row_offset: i8,
     ) -> Result<Position, &'static str> {
-        let position = position.into();
-        let row = (position.row as i8) + row_offset;
-        let c: i8 = position.column.into();
-        let column = c + column_offset;
-
-        Board::position_in_bounds(column, row)
+        Square::from(position.into())
+            .offset(column_offset, row_offset)
+            .map(Position::from)
+            .ok_or("Invalid position")
     }
 
     fn pawn_moves<P: Into<Position>>(&self, position: P, piece: Piece) -> Vec<Position> {
         let position = position.into();
         let mut moves = Vec::new();
 
-        match piece {
-            Piece::WhitePawn => {
-                if position.row < 8 {
-                    moves.push(Position::new(position.column, position.row + 1));
-                }
+        let (direction, start_row, en_passant_row) = match piece {
+            Piece::WhitePawn => (1, 2, 6),
+            Piece::BlackPawn => (-1, 7, 3),
 
-                if position.row == 2 {
-                    moves.push(Position::new(position.column, 4));
+            _ => unreachable!(),
+        };
+
+        if let Ok(single) = Board::offset_in_bounds(position, 0, direction) {
+            if self.piece_at(single) == Piece::Empty {
+                moves.push(single);
+
+                if position.row == start_row {
+                    if let Ok(double) = Board::offset_in_bounds(position, 0, direction * 2) {
+                        if self.piece_at(double) == Piece::Empty {
+                            moves.push(double);
+                        }
+                    }
                 }
             }
+        }
 
-            Piece::BlackPawn => {
-                if position.row > 1 {
-                    moves.push(Position::new(position.column, position.row - 1));
-                }
+        for column_offset in &[-1, 1] {
+            if let Ok(target) = Board::offset_in_bounds(position, *column_offset, direction) {
+                let captured = self.piece_at(target);
+                let en_passant = target.row == en_passant_row && self.en_passant() == Some(target);
 
-                if position.row == 7 {
-                    moves.push(Position::new(position.column, 5));
+                if (captured != Piece::Empty && captured.color() != piece.color()) || en_passant {
+                    moves.push(target);
                 }
             }
-
-            _ => unreachable!(),
         }
 
         moves
     }
 
-    fn king_moves<P: Into<Position>>(&self, position: P, _: Piece) -> Vec<Position> {
+    fn king_moves<P: Into<Position>>(&self, position: P, piece: Piece) -> Vec<Position> {
         let position = position.into();
         let mut moves = Vec::new();
 
@@ -104,6 +256,53 @@ impl Board {
             }
         }
 
+        for position in self.castle_moves(position, piece) {
+            moves.push(position);
+        }
+
+        moves
+    }
+
+    fn castle_moves(&self, position: Position, piece: Piece) -> Vec<Position> {
+        let mut moves = Vec::new();
+        let color = piece.color();
+        let (row, rook) = match color {
+            Color::White => (1, Piece::WhiteRook),
+            Color::Black => (8, Piece::BlackRook),
+        };
+
+        if position != Position::new(Column::E, row) || self.attacked_by(position, color.opposite())
+        {
+            return moves;
+        }
+
+        let empty = |columns: &[Column]| {
+            columns
+                .iter()
+                .all(|c| self.piece_at(Position::new(*c, row)) == Piece::Empty)
+        };
+        let safe = |columns: &[Column]| {
+            columns
+                .iter()
+                .all(|c| !self.attacked_by(Position::new(*c, row), color.opposite()))
+        };
+
+        if self.castling().kingside(color)
+            && self.piece_at(Position::new(Column::H, row)) == rook
+            && empty(&[Column::F, Column::G])
+            && safe(&[Column::F, Column::G])
+        {
+            moves.push(Position::new(Column::G, row));
+        }
+
+        if self.castling().queenside(color)
+            && self.piece_at(Position::new(Column::A, row)) == rook
+            && empty(&[Column::B, Column::C, Column::D])
+            && safe(&[Column::C, Column::D])
+        {
+            moves.push(Position::new(Column::C, row));
+        }
+
         moves
     }
 
@@ -114,9 +313,19 @@ impl Board {
         color: Color,
     ) -> Vec<Position> {
         let mut moves = Vec::new();
+        let piece = self.piece_at(start);
 
         for position in positions {
-            let board = self.hypothetical_move(start, position);
+            let mut board = self.hypothetical_move(start, position);
+
+            // an en passant capture also takes the pawn beside the start square
+            if (piece == Piece::WhitePawn || piece == Piece::BlackPawn)
+                && start.column != position.column
+                && self.piece_at(position) == Piece::Empty
+            {
+                board.add_piece(Piece::Empty, Position::new(position.column, start.row));
+            }
+
             if !board.in_check(color) {
                 moves.push(position);
             }
@@ -177,7 +386,17 @@ impl Board {
         let position = position.into();
         let mut moves = Vec::new();
 
-        for offset in &[
+        for offset in Board::knight_offsets() {
+            if let Ok(position) = Board::offset_in_bounds(position, offset.0, offset.1) {
+                moves.push(position);
+            }
+        }
+
+        moves
+    }
+
+    fn knight_offsets() -> Vec<(i8, i8)> {
+        vec![
             (2, 1),
             (1, 2),
             (-2, 1),
@@ -186,13 +405,7 @@ impl Board {
             (1, -2),
             (-2, -1),
             (-1, -2),
-        ] {
-            if let Ok(position) = Board::offset_in_bounds(position, offset.0, offset.1) {
-                moves.push(position);
-            }
-        }
-
-        moves
+        ]
     }
 
     fn projection_moves<P: Into<Position>>(
@@ -304,6 +517,7 @@ impl Board {
 #[cfg(test)]
 mod tests {
     use board::{Board, Color, Piece};
+    use movement::Move;
     use position::Position;
 
     macro_rules! assert_movement {
@@ -373,7 +587,36 @@ mod tests {
 
     #[test]
     fn white_en_passant() {
-        assert!(false);
+        let mut board = Board::empty();
+        board.add_piece(Piece::WhitePawn, "e5");
+        board.add_piece(Piece::BlackPawn, "d7");
+        board.add_piece(Piece::BlackPawn, "f6");
+        board.to_move = Color::Black;
+
+        board.move_piece("d7", "d5");
+        assert_eq!(board.to_move, Color::White);
+
+        assert_board_movement!(board, "e5", "e6", "d6", "f6");
+
+        board.move_piece("e5", "d6");
+        assert_eq!(board.piece_at("d6"), Piece::WhitePawn);
+        assert_eq!(board.piece_at("d5"), Piece::Empty);
+    }
+
+    #[test]
+    fn en_passant_expires() {
+        let mut board = Board::empty();
+        board.add_piece(Piece::WhitePawn, "e5");
+        board.add_piece(Piece::BlackPawn, "d7");
+        board.add_piece(Piece::BlackKing, "h8");
+        board.add_piece(Piece::WhiteKing, "a1");
+        board.to_move = Color::Black;
+
+        board.move_piece("d7", "d5");
+        board.move_piece("a1", "a2");
+        board.move_piece("h8", "h7");
+
+        assert_board_movement!(board, "e5", "e6");
     }
 
     #[test]
@@ -444,7 +687,66 @@ mod tests {
 
     #[test]
     fn castle() {
-        assert!(false);
+        let mut board = Board::empty();
+        board.add_piece(Piece::WhiteKing, "e1");
+        board.add_piece(Piece::WhiteRook, "a1");
+        board.add_piece(Piece::WhiteRook, "h1");
+
+        assert_board_movement!(board, "e1", "d1", "d2", "e2", "f2", "f1", "g1", "c1");
+
+        board.move_piece("e1", "g1");
+        assert_eq!(board.piece_at("g1"), Piece::WhiteKing);
+        assert_eq!(board.piece_at("f1"), Piece::WhiteRook);
+        assert_eq!(board.piece_at("h1"), Piece::Empty);
+        assert!(!board.castling().kingside(Color::White));
+        assert!(!board.castling().queenside(Color::White));
+
+        let mut board = Board::empty();
+        board.add_piece(Piece::BlackKing, "e8");
+        board.add_piece(Piece::BlackRook, "a8");
+        board.to_move = Color::Black;
+
+        board.move_piece("e8", "c8");
+        assert_eq!(board.piece_at("c8"), Piece::BlackKing);
+        assert_eq!(board.piece_at("d8"), Piece::BlackRook);
+        assert_eq!(board.piece_at("a8"), Piece::Empty);
+    }
+
+    #[test]
+    fn cant_castle_through_check() {
+        let mut board = Board::empty();
+        board.add_piece(Piece::WhiteKing, "e1");
+        board.add_piece(Piece::WhiteRook, "a1");
+        board.add_piece(Piece::WhiteRook, "h1");
+        board.add_piece(Piece::BlackRook, "f8");
+        board.add_piece(Piece::BlackKnight, "b3");
+
+        assert_board_movement!(board, "e1", "d1", "e2");
+
+        let mut board = Board::empty();
+        board.add_piece(Piece::WhiteKing, "e1");
+        board.add_piece(Piece::WhiteRook, "h1");
+        board.add_piece(Piece::BlackRook, "e8");
+
+        assert_board_movement!(board, "e1", "d1", "d2", "f2", "f1");
+    }
+
+    #[test]
+    fn moving_the_rook_loses_castling() {
+        let mut board = Board::empty();
+        board.add_piece(Piece::WhiteKing, "e1");
+        board.add_piece(Piece::WhiteRook, "a1");
+        board.add_piece(Piece::WhiteRook, "h1");
+        board.add_piece(Piece::BlackKing, "e8");
+
+        board.move_piece("h1", "h2");
+        board.move_piece("e8", "e7");
+        board.move_piece("h2", "h1");
+        board.move_piece("e7", "e8");
+
+        assert!(!board.castling().kingside(Color::White));
+        assert!(board.castling().queenside(Color::White));
+        assert_board_movement!(board, "e1", "d1", "d2", "e2", "f2", "f1", "c1");
     }
 
     #[test]
@@ -509,12 +811,46 @@ mod tests {
 
     #[test]
     fn black_pawn_exchange() {
-        assert!(false);
+        let mut board = Board::empty();
+        board.add_piece(Piece::BlackPawn, "c2");
+        board.add_piece(Piece::WhiteRook, "d1");
+        board.to_move = Color::Black;
+
+        let moves = board.legal_moves();
+        assert_eq!(moves.len(), 8);
+        assert!(moves.iter().all(|m| m.promotion.is_some()));
+
+        board.make_move(Move::promote(
+            "c2".into(),
+            "d1".into(),
+            Piece::BlackPawn,
+            Piece::BlackKnight,
+        ));
+        assert_eq!(board.piece_at("d1"), Piece::BlackKnight);
+        assert_eq!(board.piece_at("c2"), Piece::Empty);
     }
 
     #[test]
     fn white_pawn_exchange() {
-        assert!(false);
+        let mut board = Board::empty();
+        board.add_piece(Piece::WhitePawn, "a7");
+        board.add_piece(Piece::BlackKnight, "a8");
+
+        assert_board_no_movement!(board, "a7");
+
+        board.add_piece(Piece::BlackKnight, "b8");
+        let moves = board.legal_moves();
+        let mut promotions: Vec<Piece> = moves.iter().filter_map(|m| m.promotion).collect();
+        promotions.sort_by_key(|p| *p as u8);
+        assert_eq!(
+            promotions,
+            vec![
+                Piece::WhiteRook,
+                Piece::WhiteKnight,
+                Piece::WhiteBishop,
+                Piece::WhiteQueen,
+            ]
+        );
     }
 
     #[test]
@@ -642,6 +978,126 @@ mod tests {
 
     #[test]
     fn take_a_piece() {
-        assert!(false);
+        let mut board = Board::empty();
+        board.add_piece(Piece::WhitePawn, "e4");
+        board.add_piece(Piece::BlackPawn, "d5");
+        board.add_piece(Piece::BlackPawn, "e5");
+
+        assert_board_movement!(board, "e4", "d5");
+
+        board.move_piece("e4", "d5");
+        assert_eq!(board.piece_at("d5"), Piece::WhitePawn);
+        assert_eq!(board.piece_at("e4"), Piece::Empty);
+    }
+
+    #[test]
+    fn pawns_are_blocked() {
+        let mut board = Board::empty();
+        board.add_piece(Piece::WhitePawn, "e2");
+        board.add_piece(Piece::BlackKnight, "e3");
+        assert_board_no_movement!(board, "e2");
+
+        let mut board = Board::empty();
+        board.add_piece(Piece::WhitePawn, "e2");
+        board.add_piece(Piece::BlackKnight, "e4");
+        assert_board_movement!(board, "e2", "e3");
+    }
+
+    #[test]
+    fn pawn_pushes_do_not_give_check() {
+        let mut board = Board::empty();
+        board.add_piece(Piece::WhiteKing, "e3");
+        board.add_piece(Piece::BlackPawn, "e4");
+
+        assert!(!board.in_check(Color::White));
+    }
+
+    #[test]
+    fn legal_moves_from_the_start() {
+        let board = Board::default();
+        assert_eq!(board.legal_moves().len(), 20);
+
+        let mut board = Board::default();
+        board.move_piece("e2", "e4");
+        assert_eq!(board.legal_moves().len(), 20);
+        assert!(board
+            .legal_moves()
+            .iter()
+            .all(|m| m.piece.color() == Color::Black));
+    }
+
+    #[test]
+    fn pinned_pieces_stay_put() {
+        let board = board!(
+            "
+            . . . . r . . k
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . N . . .
+            . . . . . . . .
+            . . . . K . . .
+            "
+        );
+
+        let moves = board.legal_moves();
+        assert!(moves.iter().all(|m| m.piece == Piece::WhiteKing));
+        assert_eq!(moves.len(), 5);
+    }
+
+    #[test]
+    fn remembered_moves() {
+        let board = board!(
+            "
+            k . . . r . . .
+            . . . . . . . P
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . N . . .
+            . . . . . . . .
+            . . . . K . . .
+            "
+        );
+
+        for m in board.legal_moves() {
+            assert!(board.is_legal(&m));
+        }
+
+        let pinned = Move::new("e3".into(), "c4".into(), Piece::WhiteKnight);
+        let unpromoted = Move::new("h7".into(), "h8".into(), Piece::WhitePawn);
+        let wrong_piece = Move::new("e1".into(), "d1".into(), Piece::WhiteQueen);
+        let too_far = Move::new("e1".into(), "e3".into(), Piece::WhiteKing);
+        for m in &[pinned, unpromoted, wrong_piece, too_far] {
+            assert!(!board.is_legal(m));
+        }
+        assert!(board.is_legal(&Move::new("e1".into(), "d1".into(), Piece::WhiteKing)));
+        assert!(board.is_legal(&Move::promote(
+            "h7".into(),
+            "h8".into(),
+            Piece::WhitePawn,
+            Piece::WhiteKnight
+        )));
+    }
+
+    #[test]
+    fn any_legal_move() {
+        assert!(Board::default().has_legal_move());
+
+        let stalemate = board!(
+            "
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            p . p . . . . p
+            P . P . . k . P
+            R B . . . . . K
+            "
+        );
+        assert!(!stalemate.has_legal_move());
+        assert!(stalemate.stalemate());
     }
 }
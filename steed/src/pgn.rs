@@ -0,0 +1,1027 @@
+use board::{Board, Color};
+use movement::Move;
+use san::SanError;
+use std::fmt;
+use std::io;
+use std::io::{BufRead, Lines, Write};
+use tree::{GameTree, NodeId};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Unknown,
+}
+
+impl GameResult {
+    fn from_token(token: &str) -> Option<GameResult> {
+        match token {
+            "1-0" => Some(GameResult::WhiteWins),
+            "0-1" => Some(GameResult::BlackWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            "*" => Some(GameResult::Unknown),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GameResult::WhiteWins => write!(f, "1-0"),
+            GameResult::BlackWins => write!(f, "0-1"),
+            GameResult::Draw => write!(f, "1/2-1/2"),
+            GameResult::Unknown => write!(f, "*"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Game {
+    pub tags: Vec<(String, String)>,
+    /// Every move of the game along with its variations, comments and NAGs.
+    pub tree: GameTree,
+    pub result: GameResult,
+}
+
+impl Game {
+    /// Builds a game from the moves recorded on `board`, which must have been
+    /// played from the standard starting position.
+    pub fn from_board(board: &Board) -> Game {
+        let result = if board.checkmate(board.to_move) {
+            match board.to_move {
+                Color::White => GameResult::BlackWins,
+                Color::Black => GameResult::WhiteWins,
+            }
+        } else if board.stalemate() {
+            GameResult::Draw
+        } else {
+            GameResult::Unknown
+        };
+
+        let mut tree = GameTree::new(Board::default());
+        let mut id = tree.root();
+        for m in board.history() {
+            id = tree.add_move(id, *m);
+        }
+
+        Game {
+            tags: Vec::new(),
+            tree,
+            result,
+        }
+    }
+
+    pub fn moves(&self) -> Vec<Move> {
+        self.tree.mainline_moves()
+    }
+
+    /// The position at the end of the main line.
+    pub fn board(&self) -> Board {
+        match self.tree.mainline().last() {
+            Some(&id) => self.tree.board_at(id),
+            None => self.tree.start().clone(),
+        }
+    }
+
+    /// Sets a tag, replacing any earlier value with the same name.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|tag| tag.0 == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|tag| tag.0 == name)
+            .map(|tag| tag.1.as_str())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PgnError {
+    /// Index of the game in the stream, starting at 0.
+    pub game: usize,
+    /// Index of the offending move counted from the start of the game, along
+    /// its variation if it is in one.
+    pub ply: Option<usize>,
+    pub kind: PgnErrorKind,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum PgnErrorKind {
+    Io(String),
+    Tag(String),
+    Move(String, SanError),
+    UnterminatedComment,
+    UnbalancedVariation,
+    /// Games starting from a `FEN` tag can't be replayed yet.
+    UnsupportedSetup,
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "game {}", self.game + 1)?;
+
+        if let Some(ply) = self.ply {
+            let dots = if ply % 2 == 0 { "." } else { "..." };
+            write!(f, ", move {}{}", ply / 2 + 1, dots)?;
+        }
+
+        match self.kind {
+            PgnErrorKind::Io(ref e) => write!(f, ": {}", e),
+            PgnErrorKind::Tag(ref line) => write!(f, ": malformed tag {}", line),
+            PgnErrorKind::Move(ref san, e) => write!(f, " {}: {}", san, e),
+            PgnErrorKind::UnterminatedComment => write!(f, ": unterminated comment"),
+            PgnErrorKind::UnbalancedVariation => write!(f, ": unbalanced variation"),
+            PgnErrorKind::UnsupportedSetup => write!(f, ": games with a FEN tag are not supported"),
+        }
+    }
+}
+
+/// Reads games one at a time from any buffered source. An error in one game
+/// is reported and the reader carries on with the next.
+pub struct PgnReader<R: BufRead> {
+    lines: Lines<R>,
+    pending: Option<String>,
+    game: usize,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> PgnReader<R> {
+        PgnReader {
+            lines: reader.lines(),
+            pending: None,
+            game: 0,
+        }
+    }
+
+    fn error(&self, ply: Option<usize>, kind: PgnErrorKind) -> PgnError {
+        PgnError {
+            game: self.game,
+            ply,
+            kind,
+        }
+    }
+
+    // Collects the tag lines and movetext of the next game. A game ends at its
+    // result, or failing that where the next tag section starts.
+    fn read_game(&mut self) -> Option<Result<(Vec<String>, String), PgnError>> {
+        let mut tags = Vec::new();
+        let mut movetext = String::new();
+        let mut in_comment = false;
+
+        loop {
+            let line = match self.pending.take() {
+                Some(line) => line,
+                None => match self.lines.next() {
+                    Some(Ok(line)) => line,
+                    Some(Err(e)) => {
+                        return Some(Err(self.error(None, PgnErrorKind::Io(e.to_string()))))
+                    }
+                    None => break,
+                },
+            };
+
+            // escape lines are reserved for other programs
+            if line.starts_with('%') {
+                continue;
+            }
+
+            let trimmed = line.trim();
+            if !in_comment && trimmed.starts_with('[') {
+                if !movetext.is_empty() {
+                    self.pending = Some(line);
+                    break;
+                }
+
+                tags.push(trimmed.to_string());
+                continue;
+            }
+
+            if trimmed.is_empty() && !in_comment {
+                continue;
+            }
+
+            movetext.push_str(&line);
+            movetext.push('\n');
+
+            if scan_for_result(&line, &mut in_comment) {
+                break;
+            }
+        }
+
+        if tags.is_empty() && movetext.is_empty() {
+            None
+        } else {
+            Some(Ok((tags, movetext)))
+        }
+    }
+
+    fn parse_game(&self, tag_lines: &[String], movetext: &str) -> Result<Game, PgnError> {
+        let mut tags = Vec::new();
+        for line in tag_lines {
+            tags.push(
+                parse_tag(line).ok_or_else(|| self.error(None, PgnErrorKind::Tag(line.clone())))?,
+            );
+        }
+
+        if tags.iter().any(|tag| tag.0 == "FEN") {
+            return Err(self.error(None, PgnErrorKind::UnsupportedSetup));
+        }
+
+        let tokens = tokenize(movetext).map_err(|kind| self.error(None, kind))?;
+
+        let mut tree = GameTree::new(Board::default());
+        let mut current = tree.root();
+        let mut board = Board::default();
+        let mut result = None;
+
+        // where to pick up again once the innermost variation closes
+        let mut stack: Vec<(NodeId, Board)> = Vec::new();
+        // comments between an opening parenthesis and the variation's first
+        // move belong in front of that move
+        let mut variation_start = false;
+        let mut comments_before = Vec::new();
+        let mut annotations_before = Vec::new();
+
+        for token in tokens {
+            let ply = board.history().len();
+
+            match token {
+                Token::Open => {
+                    let parent = match tree.node(current).parent {
+                        Some(parent) => parent,
+                        None => {
+                            return Err(self.error(Some(ply), PgnErrorKind::UnbalancedVariation))
+                        }
+                    };
+
+                    stack.push((current, board));
+                    board = tree.board_at(parent);
+                    current = parent;
+                    variation_start = true;
+                }
+                Token::Close => match stack.pop() {
+                    Some((node, b)) => {
+                        current = node;
+                        board = b;
+                        variation_start = false;
+                    }
+                    None => return Err(self.error(Some(ply), PgnErrorKind::UnbalancedVariation)),
+                },
+                Token::Comment(text) => {
+                    let (text, annotations) = extract_annotations(&text);
+
+                    if variation_start {
+                        if !text.is_empty() {
+                            comments_before.push(text);
+                        }
+                        annotations_before.extend(annotations);
+                    } else {
+                        let node = tree.node_mut(current);
+                        if !text.is_empty() {
+                            node.comments.push(text);
+                        }
+                        for (name, value) in annotations {
+                            node.set_annotation(&name, &value);
+                        }
+                    }
+                }
+                Token::Nag(nag) => tree.node_mut(current).nags.push(nag),
+                Token::Result(r) => {
+                    if stack.is_empty() {
+                        result = Some(r);
+                    }
+                }
+                Token::Move(san) => {
+                    let m = board
+                        .parse_san_move(&san)
+                        .map_err(|e| self.error(Some(ply), PgnErrorKind::Move(san.clone(), e)))?;
+                    board.make_move(m);
+                    current = tree.add_move(current, m);
+
+                    let node = tree.node_mut(current);
+                    node.comments_before.append(&mut comments_before);
+                    for (name, value) in annotations_before.drain(..) {
+                        node.set_annotation(&name, &value);
+                    }
+                    variation_start = false;
+                }
+            }
+        }
+
+        if !stack.is_empty() {
+            return Err(self.error(
+                Some(board.history().len()),
+                PgnErrorKind::UnbalancedVariation,
+            ));
+        }
+
+        let result = result
+            .or_else(|| {
+                tags.iter()
+                    .find(|tag| tag.0 == "Result")
+                    .and_then(|tag| GameResult::from_token(&tag.1))
+            })
+            .unwrap_or(GameResult::Unknown);
+
+        Ok(Game { tags, tree, result })
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<Game, PgnError>;
+
+    fn next(&mut self) -> Option<Result<Game, PgnError>> {
+        let game = match self.read_game()? {
+            Ok((tags, movetext)) => self.parse_game(&tags, &movetext),
+            Err(e) => Err(e),
+        };
+
+        self.game += 1;
+        Some(game)
+    }
+}
+
+/// Writes games as PGN: the Seven Tag Roster followed by any other tags,
+/// then the movetext wrapped at 80 columns.
+pub struct PgnWriter<W: Write> {
+    writer: W,
+    games: usize,
+}
+
+const ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+const LINE_WIDTH: usize = 80;
+
+impl<W: Write> PgnWriter<W> {
+    pub fn new(writer: W) -> PgnWriter<W> {
+        PgnWriter { writer, games: 0 }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    pub fn write_game(&mut self, game: &Game) -> io::Result<()> {
+        if self.games > 0 {
+            writeln!(self.writer)?;
+        }
+        self.games += 1;
+
+        for &(name, default) in ROSTER.iter() {
+            let value = if name == "Result" {
+                game.result.to_string()
+            } else {
+                game.tag(name).unwrap_or(default).to_string()
+            };
+            writeln!(self.writer, "[{} \"{}\"]", name, escape_tag(&value))?;
+        }
+
+        for tag in &game.tags {
+            if !ROSTER.iter().any(|r| r.0 == tag.0) {
+                writeln!(self.writer, "[{} \"{}\"]", tag.0, escape_tag(&tag.1))?;
+            }
+        }
+        writeln!(self.writer)?;
+
+        let mut line = String::new();
+        for word in movetext_words(game) {
+            if !line.is_empty() && line.len() + 1 + word.len() > LINE_WIDTH {
+                writeln!(self.writer, "{}", line)?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&word);
+        }
+
+        writeln!(self.writer, "{}", line)
+    }
+}
+
+fn movetext_words(game: &Game) -> Vec<String> {
+    let tree = &game.tree;
+    let root = tree.node(tree.root());
+
+    let mut words = comment_words(&root.annotations, &root.comments);
+    let needs_number = !words.is_empty();
+    write_line(
+        tree,
+        tree.root(),
+        tree.start().clone(),
+        needs_number,
+        &mut words,
+    );
+
+    words.push(game.result.to_string());
+    words
+}
+
+// Writes the main line after `id`, putting the variations at each branch in
+// parentheses right after the main move they replace.
+fn write_line(
+    tree: &GameTree,
+    id: NodeId,
+    board: Board,
+    needs_number: bool,
+    words: &mut Vec<String>,
+) {
+    let mut id = id;
+    let mut board = board;
+    let mut needs_number = needs_number;
+
+    while let Some(&main) = tree.node(id).children.first() {
+        let children = &tree.node(id).children;
+        let commented = write_move(tree, main, &board, needs_number, words);
+
+        for &variation in &children[1..] {
+            let mut line = Vec::new();
+            write_move(tree, variation, &board, true, &mut line);
+
+            let mut after = board.clone();
+            after.make_move(tree.node(variation).m.unwrap());
+            write_line(tree, variation, after, false, &mut line);
+
+            line[0].insert(0, '(');
+            line.last_mut().unwrap().push(')');
+            words.extend(line);
+        }
+
+        needs_number = commented || children.len() > 1;
+        board.make_move(tree.node(main).m.unwrap());
+        id = main;
+    }
+}
+
+// Returns whether a comment follows the move, in which case a black reply
+// has to repeat the move number.
+fn write_move(
+    tree: &GameTree,
+    id: NodeId,
+    board: &Board,
+    needs_number: bool,
+    words: &mut Vec<String>,
+) -> bool {
+    let node = tree.node(id);
+    let m = node.m.unwrap();
+    let number = board.history().len() / 2 + 1;
+
+    let before = comment_words(&[], &node.comments_before);
+    let needs_number = needs_number || !before.is_empty();
+    words.extend(before);
+
+    // move numbers stay on the same line as their move
+    let san = board.move_to_san(&m);
+    if board.to_move == Color::White {
+        words.push(format!("{}. {}", number, san));
+    } else if needs_number {
+        words.push(format!("{}... {}", number, san));
+    } else {
+        words.push(san);
+    }
+
+    for nag in &node.nags {
+        words.push(format!("${}", nag));
+    }
+
+    let after = comment_words(&node.annotations, &node.comments);
+    let commented = !after.is_empty();
+    words.extend(after);
+
+    commented
+}
+
+fn comment_words(annotations: &[(String, String)], comments: &[String]) -> Vec<String> {
+    let mut parts: Vec<String> = annotations
+        .iter()
+        .map(|a| format!("[%{} {}]", a.0, a.1))
+        .collect();
+    parts.extend(comments.iter().cloned());
+
+    if parts.is_empty() {
+        return Vec::new();
+    }
+
+    let comment = format!("{{{}}}", parts.join(" ").replace('}', ")"));
+    comment.split(' ').map(|w| w.to_string()).collect()
+}
+
+fn escape_tag(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Pulls commands such as `[%clk 0:01:00]` out of a comment, returning what
+// is left of the comment alongside each command's name and value.
+fn extract_annotations(comment: &str) -> (String, Vec<(String, String)>) {
+    let mut text = String::new();
+    let mut annotations = Vec::new();
+    let mut rest = comment;
+
+    while let Some(start) = rest.find("[%") {
+        let end = match rest[start..].find(']') {
+            Some(end) => start + end,
+            None => break,
+        };
+
+        let command = rest[start + 2..end].trim();
+        let (name, value) = match command.find(char::is_whitespace) {
+            Some(split) => (&command[..split], command[split..].trim()),
+            None => (command, ""),
+        };
+        annotations.push((name.to_string(), value.to_string()));
+
+        text.push_str(&rest[..start]);
+        text.push(' ');
+        rest = &rest[end + 1..];
+    }
+    text.push_str(rest);
+
+    (normalize_whitespace(&text), annotations)
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    Comment(String),
+    Nag(u8),
+    Open,
+    Close,
+    Result(GameResult),
+    Move(String),
+}
+
+// Tracks brace comments across lines and reports whether a result token
+// appears outside of them.
+fn scan_for_result(line: &str, in_comment: &mut bool) -> bool {
+    let mut word = String::new();
+
+    for c in line.chars().chain(Some(' ')) {
+        if *in_comment {
+            if c == '}' {
+                *in_comment = false;
+            }
+            continue;
+        }
+
+        match c {
+            '{' | ';' | ' ' | '\t' | '(' | ')' => {
+                if GameResult::from_token(&word).is_some() {
+                    return true;
+                }
+                word.clear();
+
+                match c {
+                    '{' => *in_comment = true,
+                    ';' => return false,
+                    _ => {}
+                }
+            }
+            _ => word.push(c),
+        }
+    }
+
+    false
+}
+
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let inner = line
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .trim();
+    let split = inner.find(char::is_whitespace)?;
+    let (name, value) = inner.split_at(split);
+    let value = value.trim();
+
+    if !value.starts_with('"') || !value.ends_with('"') || value.len() < 2 {
+        return None;
+    }
+
+    let mut unescaped = String::new();
+    let mut escaped = false;
+    for c in value[1..value.len() - 1].chars() {
+        if c == '\\' && !escaped {
+            escaped = true;
+            continue;
+        }
+        escaped = false;
+        unescaped.push(c);
+    }
+
+    Some((name.to_string(), unescaped))
+}
+
+fn tokenize(movetext: &str) -> Result<Vec<Token>, PgnErrorKind> {
+    let mut tokens = Vec::new();
+    let mut chars = movetext.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => text.push(c),
+                        None => return Err(PgnErrorKind::UnterminatedComment),
+                    }
+                }
+                tokens.push(Token::Comment(normalize_whitespace(&text)));
+            }
+            ';' => {
+                let mut text = String::new();
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                    text.push(c);
+                }
+                tokens.push(Token::Comment(normalize_whitespace(&text)));
+            }
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            '$' => {
+                let mut digits = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !c.is_ascii_digit() {
+                        break;
+                    }
+                    digits.push(c);
+                    chars.next();
+                }
+                if let Ok(nag) = digits.parse() {
+                    tokens.push(Token::Nag(nag));
+                }
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "{}();$".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+
+                if let Some(result) = GameResult::from_token(&word) {
+                    tokens.push(Token::Result(result));
+                    continue;
+                }
+
+                // drop move numbers such as `12.` or `12...`, which are often
+                // written without a space before the move
+                let san = match word.rfind('.') {
+                    Some(i) if word[..i].chars().all(|c| c.is_ascii_digit() || c == '.') => {
+                        word[i + 1..].to_string()
+                    }
+                    _ => word,
+                };
+
+                if san.is_empty() || san.chars().all(|c| c.is_ascii_digit()) {
+                    continue;
+                }
+
+                // suffix annotations are the same as the first six NAGs
+                let glyph_start = san.trim_end_matches(&['!', '?'][..]).len();
+                let nag = match &san[glyph_start..] {
+                    "!" => Some(1),
+                    "?" => Some(2),
+                    "!!" => Some(3),
+                    "??" => Some(4),
+                    "!?" => Some(5),
+                    "?!" => Some(6),
+                    _ => None,
+                };
+
+                // a glyph written apart from its move, as in `e4 !`, belongs to
+                // the move before it
+                if glyph_start > 0 {
+                    tokens.push(Token::Move(san[..glyph_start].to_string()));
+                }
+                if let Some(nag) = nag {
+                    tokens.push(Token::Nag(nag));
+                }
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::Piece;
+    use std::time::Duration;
+
+    const GAMES: &str = r#"[Event "F/S Return Match"]
+[Site "Belgrade, Serbia JUG"]
+[Date "1992.11.04"]
+[Round "29"]
+[White "Fischer, Robert J."]
+[Black "Spassky, Boris V."]
+[Result "1/2-1/2"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 {This opening is called the Ruy Lopez.} 3... a6
+4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O 9. h3 Nb8 10. d4 Nbd7
+11. c4 c6 12. cxb5 axb5 13. Nc3 Bb7 14. Bg5 b4 15. Nb1 h6 16. Bh4 c5 17. dxe5
+Nxe4 18. Bxe7 Qxe7 19. exd6 Qf6 20. Nbd2 Nxd6 21. Nc4 Nxc4 22. Bxc4 Nb6
+23. Ne5 Rae8 24. Bxf7+ Rxf7 25. Nxf7 Rxe1+ 26. Qxe1 Kxf7 27. Qe3 Qg5 28. Qxg5
+hxg5 29. b3 Ke6 30. a3 Kd6 31. axb4 cxb4 32. Ra5 Nd5 33. f3 Bc8 34. Kf2 Bf5
+35. Ra7 g6 36. Ra6+ Kc5 37. Ke1 Nf4 38. g3 Nxh3 39. Kd2 Kb5 40. Rd6 Kc5 41. Ra6
+Nf2 42. g4 Bd3 43. Re6 1/2-1/2
+
+[Event "Casual"]
+[White "Anderssen"]
+[Black "Kieseritzky"]
+[Result "1-0"]
+
+1.e4 e5 2.f4 exf4 3.Bc4 Qh4+ 4.Kf1 b5 5.Bxb5 Nf6 6.Nf3 Qh6 7.d3 Nh5 8.Nh4 Qg5
+9.Nf5 c6 10.g4 Nf6 11.Rg1 cxb5 12.h4 Qg6 13.h5 Qg5 14.Qf3 Ng8 15.Bxf4 Qf6
+16.Nc3 Bc5 17.Nd5 Qxb2 18.Bd6 Bxg1 19. e5 Qxa1+ 20. Ke2 Na6 21.Nxg7+ Kd8
+22.Qf6+ Nxf6 23.Be7# 1-0
+"#;
+
+    #[test]
+    fn read_games() {
+        let games: Vec<Game> = PgnReader::new(GAMES.as_bytes())
+            .map(|game| game.unwrap())
+            .collect();
+
+        assert_eq!(games.len(), 2);
+
+        assert_eq!(games[0].tag("White"), Some("Fischer, Robert J."));
+        assert_eq!(games[0].tag("ECO"), None);
+        assert_eq!(games[0].tags.len(), 7);
+        assert_eq!(games[0].moves().len(), 85);
+        assert_eq!(games[0].result, GameResult::Draw);
+
+        let mainline = games[0].tree.mainline();
+        assert_eq!(
+            games[0].tree.node(mainline[4]).comments,
+            vec!["This opening is called the Ruy Lopez.".to_string()]
+        );
+
+        let board = games[1].board();
+        assert_eq!(games[1].moves().len(), 45);
+        assert_eq!(games[1].result, GameResult::WhiteWins);
+        assert_eq!(board.piece_at("e7"), Piece::WhiteBishop);
+        assert!(board.checkmate(board.to_move));
+    }
+
+    #[test]
+    fn tolerate_quirks() {
+        let pgn = "% exported by some tool\n\
+                   1. e4 ; king's pawn\n\
+                   e5 (1... c5 2. Nf3 (2. c3) d6) 2. Nf3 $1 {multi\n\
+                   line comment} Nc6 *\n\
+                   \n\
+                   [White \"Quote \\\"Me\\\"\"]\n\
+                   1. d4 d5\n";
+
+        let games: Vec<Game> = PgnReader::new(pgn.as_bytes())
+            .map(|game| game.unwrap())
+            .collect();
+
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].tags.len(), 0);
+        assert_eq!(games[0].moves().len(), 4);
+        assert_eq!(games[0].result, GameResult::Unknown);
+
+        let tree = &games[0].tree;
+        let mainline = tree.mainline();
+        assert_eq!(
+            tree.node(mainline[0]).comments,
+            vec!["king's pawn".to_string()]
+        );
+        assert_eq!(
+            tree.node(mainline[2]).comments,
+            vec!["multi line comment".to_string()]
+        );
+        assert_eq!(tree.node(mainline[2]).nags, vec![1]);
+
+        let c5 = tree.node(mainline[0]).children[1];
+        let sicilian = &tree.node(c5).children;
+        assert_eq!(sicilian.len(), 2);
+        assert_eq!(tree.moves_to(sicilian[1])[2].to_uci(), "c2c3");
+        assert_eq!(
+            tree.node(tree.node(sicilian[0]).children[0])
+                .m
+                .unwrap()
+                .to_uci(),
+            "d7d6"
+        );
+
+        assert_eq!(games[1].tag("White"), Some("Quote \"Me\""));
+        assert_eq!(games[1].moves().len(), 2);
+    }
+
+    #[test]
+    fn report_errors() {
+        let pgn = "[Event \"one\"]\n\n1. e4 e5 2. Ke3 Nc6 1-0\n\n\
+                   [Event \"two\"]\n\n1. d4 d5 2. c4 0-1\n\n\
+                   [Event \"three\"]\n\n1. e4 {never closed\n";
+
+        let mut reader = PgnReader::new(pgn.as_bytes());
+
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.game, 0);
+        assert_eq!(error.ply, Some(2));
+        assert_eq!(
+            error.kind,
+            PgnErrorKind::Move("Ke3".to_string(), SanError::Illegal)
+        );
+        assert_eq!(error.to_string(), "game 1, move 2. Ke3: illegal SAN move");
+
+        let game = reader.next().unwrap().unwrap();
+        assert_eq!(game.tag("Event"), Some("two"));
+        assert_eq!(game.result, GameResult::BlackWins);
+
+        let error = reader.next().unwrap().unwrap_err();
+        assert_eq!(error.game, 2);
+        assert_eq!(error.kind, PgnErrorKind::UnterminatedComment);
+
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn read_clocks() {
+        let pgn = "1. e4 { [%clk 0:02:59] } e5 {[%clk 0:02:58.5] solid [%eval 0.3]} *";
+        let game = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+        let mainline = game.tree.mainline();
+        let e4 = game.tree.node(mainline[0]);
+        let e5 = game.tree.node(mainline[1]);
+
+        assert_eq!(e4.clock(), Some(Duration::from_secs(179)));
+        assert!(e4.comments.is_empty());
+        assert_eq!(e5.clock(), Some(Duration::from_millis(178_500)));
+        assert_eq!(e5.annotation("eval"), Some("0.3"));
+        assert_eq!(e5.comments, vec!["solid".to_string()]);
+    }
+
+    #[test]
+    fn read_nags() {
+        let pgn = "1. e4! e5?! 2. Nf3 $14 $32 Nc6?? *";
+        let game = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+        let nags: Vec<Vec<u8>> = game
+            .tree
+            .mainline()
+            .iter()
+            .map(|&id| game.tree.node(id).nags.clone())
+            .collect();
+
+        assert_eq!(nags, vec![vec![1], vec![6], vec![14, 32], vec![4]]);
+    }
+
+    #[test]
+    fn read_detached_glyphs() {
+        let pgn = "1. e4 ! e5 !!! 2. Nf3 ?! *";
+        let game = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+        let nags: Vec<Vec<u8>> = game
+            .tree
+            .mainline()
+            .iter()
+            .map(|&id| game.tree.node(id).nags.clone())
+            .collect();
+
+        assert_eq!(nags, vec![vec![1], vec![], vec![6]]);
+    }
+
+    #[test]
+    fn read_variation_errors() {
+        let pgn = "1. e4 e5 (1... c5 2. Ke2 (2. Ke3)) *";
+        let error = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap_err();
+        assert_eq!(error.ply, Some(2));
+        assert_eq!(
+            error.kind,
+            PgnErrorKind::Move("Ke3".to_string(), SanError::Illegal)
+        );
+
+        let pgn = "(1. e4) *";
+        let error = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap_err();
+        assert_eq!(error.kind, PgnErrorKind::UnbalancedVariation);
+
+        let pgn = "1. e4 (1. d4 *";
+        let error = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap_err();
+        assert_eq!(error.kind, PgnErrorKind::UnbalancedVariation);
+    }
+
+    fn write(game: &Game) -> String {
+        let mut writer = PgnWriter::new(Vec::new());
+        writer.write_game(game).unwrap();
+        String::from_utf8(writer.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn write_recorded_game() {
+        let mut board = Board::default();
+        for san in &["f3", "e5", "g4", "Qh4"] {
+            let m = board.parse_san_move(san).unwrap();
+            board.make_move(m);
+        }
+
+        let mut game = Game::from_board(&board);
+        game.set_tag("White", "Fool");
+        game.set_tag("Event", "Casual \"blitz\"");
+        game.set_tag("ECO", "A00");
+        let mainline = game.tree.mainline();
+        game.tree
+            .node_mut(mainline[1])
+            .comments
+            .push("the losing move".to_string());
+        game.tree
+            .node_mut(mainline[0])
+            .set_clock(Duration::from_secs(3725));
+
+        assert_eq!(game.result, GameResult::BlackWins);
+        assert_eq!(
+            write(&game),
+            "[Event \"Casual \\\"blitz\\\"\"]\n\
+             [Site \"?\"]\n\
+             [Date \"????.??.??\"]\n\
+             [Round \"?\"]\n\
+             [White \"Fool\"]\n\
+             [Black \"?\"]\n\
+             [Result \"0-1\"]\n\
+             [ECO \"A00\"]\n\
+             \n\
+             1. f3 {[%clk 1:02:05]} 1... e5 {the losing move} 2. g4 Qh4# 0-1\n"
+        );
+    }
+
+    #[test]
+    fn write_wraps_lines() {
+        let game = PgnReader::new(GAMES.as_bytes()).next().unwrap().unwrap();
+        let pgn = write(&game);
+
+        assert!(pgn.lines().all(|line| line.len() <= 80));
+        assert!(pgn.contains("3. Bb5 {This opening is called the Ruy Lopez.} 3... a6"));
+        assert!(pgn.contains("\n4. Ba4 Nf6"));
+        assert!(pgn.ends_with("\n43. Re6 1/2-1/2\n"));
+
+        let again = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+        assert_eq!(again.moves(), game.moves());
+        assert_eq!(again.tags, game.tags);
+        assert_eq!(write(&again), pgn);
+    }
+
+    #[test]
+    fn write_variations() {
+        let movetext = "{Intro} 1. e4 $1 e5 (1... c5 {Sicilian} 2. Nf3 (2. c3 d5) 2... d6) \
+                        (1... e6 2. d4) 2. Nf3 {[%clk 0:01:00]} 2... Nc6 (2... d6) 1-0";
+        let game = PgnReader::new(movetext.as_bytes()).next().unwrap().unwrap();
+
+        let pgn = write(&game);
+        let written = pgn.split("\n\n").nth(1).unwrap().replace('\n', " ");
+        assert_eq!(
+            written.trim_end(),
+            movetext.split_whitespace().collect::<Vec<_>>().join(" ")
+        );
+
+        let again = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+        assert_eq!(write(&again), pgn);
+    }
+
+    #[test]
+    fn write_comments_before_variations() {
+        let movetext = "1. e4 e5 ({Or} 1... c5) 2. Nf3 *";
+        let game = PgnReader::new(movetext.as_bytes()).next().unwrap().unwrap();
+
+        let c5 = game.tree.node(game.tree.mainline()[0]).children[1];
+        assert_eq!(game.tree.node(c5).comments_before, vec!["Or".to_string()]);
+        assert!(write(&game).ends_with("1. e4 e5 ({Or} 1... c5) 2. Nf3 *\n"));
+    }
+
+    #[test]
+    fn write_several_games() {
+        let mut writer = PgnWriter::new(Vec::new());
+        for game in PgnReader::new(GAMES.as_bytes()) {
+            writer.write_game(&game.unwrap()).unwrap();
+        }
+        let pgn = String::from_utf8(writer.into_inner()).unwrap();
+
+        let games: Vec<Game> = PgnReader::new(pgn.as_bytes())
+            .map(|game| game.unwrap())
+            .collect();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[1].result, GameResult::WhiteWins);
+    }
+}
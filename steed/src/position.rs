@@ -1,3 +1,6 @@
+use std::fmt;
+use std::str::FromStr;
+
 #[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq)]
 pub struct Position {
     pub column: Column,
@@ -13,6 +16,32 @@ impl Position {
     }
 }
 
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.column, self.row)
+    }
+}
+
+impl FromStr for Position {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Position, &'static str> {
+        let mut chars = s.chars();
+        let (col, row) = match (chars.next(), chars.next(), chars.next()) {
+            (Some(col), Some(row), None) => (col, row),
+            _ => return Err("position must be exactly 2 characters"),
+        };
+
+        match (col, row) {
+            ('a'..='h', '1'..='8') => Ok(Position {
+                column: col.into(),
+                row: row.to_digit(10).unwrap() as u8,
+            }),
+            _ => Err("Invalid position"),
+        }
+    }
+}
+
 impl From<&'static str> for Position {
     fn from(s: &'static str) -> Position {
         let s = String::from(s);
@@ -90,6 +119,23 @@ pub enum Column {
     H,
 }
 
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let c = match *self {
+            Column::A => 'a',
+            Column::B => 'b',
+            Column::C => 'c',
+            Column::D => 'd',
+            Column::E => 'e',
+            Column::F => 'f',
+            Column::G => 'g',
+            Column::H => 'h',
+        };
+
+        write!(f, "{}", c)
+    }
+}
+
 impl From<char> for Column {
     fn from(c: char) -> Column {
         match c {
@@ -151,4 +197,20 @@ mod tests {
         assert_eq!(p.column, Column::B);
         assert_eq!(p.row, 2);
     }
+
+    #[test]
+    fn parse_position() {
+        assert_eq!("e4".parse(), Ok(Position::new(Column::E, 4)));
+        assert_eq!("h8".parse(), Ok(Position::new(Column::H, 8)));
+        assert!("i1".parse::<Position>().is_err());
+        assert!("a9".parse::<Position>().is_err());
+        assert!("a10".parse::<Position>().is_err());
+        assert!("".parse::<Position>().is_err());
+    }
+
+    #[test]
+    fn display_position() {
+        let p: Position = "c6".into();
+        assert_eq!(p.to_string(), "c6");
+    }
 }
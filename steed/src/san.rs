@@ -0,0 +1,384 @@
+use std::fmt;
+
+use board::{Board, Color, Piece};
+use movement::Move;
+use position::{Column, Position};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum SanError {
+    /// The text could not be read as a move at all.
+    Invalid,
+    /// The text describes a move, but no legal move matches it.
+    Illegal,
+    /// More than one legal move matches the text.
+    Ambiguous,
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SanError::Invalid => write!(f, "invalid SAN move"),
+            SanError::Illegal => write!(f, "illegal SAN move"),
+            SanError::Ambiguous => write!(f, "ambiguous SAN move"),
+        }
+    }
+}
+
+impl Board {
+    pub fn move_to_san(&self, m: &Move) -> String {
+        let mut san = String::new();
+
+        if m.is_castle() {
+            if m.end.column == Column::G {
+                san.push_str("O-O");
+            } else {
+                san.push_str("O-O-O");
+            }
+        } else {
+            let capture = self.is_capture(m);
+
+            match san_letter(m.piece) {
+                Some(letter) => {
+                    san.push(letter);
+                    san.push_str(&self.disambiguation(m));
+                }
+                None => {
+                    if capture {
+                        san.push_str(&m.start.column.to_string());
+                    }
+                }
+            }
+
+            if capture {
+                san.push('x');
+            }
+
+            san.push_str(&m.end.to_string());
+
+            if let Some(letter) = m.promotion.and_then(san_letter) {
+                san.push('=');
+                san.push(letter);
+            }
+        }
+
+        let mut board = self.clone();
+        board.make_move(*m);
+        if board.in_check(board.to_move) {
+            if board.legal_moves().is_empty() {
+                san.push('#');
+            } else {
+                san.push('+');
+            }
+        }
+
+        san
+    }
+
+    /// Reads a move in SAN, resolving it against the legal moves in this
+    /// position. Check and annotation suffixes, `e.p.`, zeros for castling,
+    /// capture marks and a missing `=` before a promotion are all accepted.
+    pub fn parse_san_move(&self, s: &str) -> Result<Move, SanError> {
+        let mut text = s.trim();
+        if let Some(index) = text.find("e.p.") {
+            text = text[..index].trim_end();
+        }
+        let text = text.trim_end_matches(&['+', '#', '!', '?'][..]);
+
+        match text.replace('0', "O").as_str() {
+            "O-O" | "OO" => return self.find_castle(Column::G),
+            "O-O-O" | "OOO" => return self.find_castle(Column::C),
+            _ => {}
+        }
+
+        let mut chars: Vec<char> = text
+            .chars()
+            .filter(|c| *c != 'x' && *c != ':' && *c != '-')
+            .collect();
+
+        let letter = match chars.first() {
+            Some(c) if "KQRBN".contains(*c) => Some(*c),
+            Some(_) => None,
+            None => return Err(SanError::Invalid),
+        };
+        if letter.is_some() {
+            chars.remove(0);
+        }
+
+        let promotion = match chars.last() {
+            Some(c) if "QRBNqrbn".contains(*c) => Some(c.to_ascii_uppercase()),
+            _ => None,
+        };
+        if promotion.is_some() {
+            chars.pop();
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+        }
+
+        if chars.len() < 2 || chars.len() > 4 {
+            return Err(SanError::Invalid);
+        }
+
+        let square: String = chars[chars.len() - 2..].iter().collect();
+        let end: Position = square.parse().map_err(|_| SanError::Invalid)?;
+
+        let mut column = None;
+        let mut row = None;
+        for c in &chars[..chars.len() - 2] {
+            match *c {
+                'a'..='h' if column.is_none() => column = Some(Column::from(*c)),
+                '1'..='8' if row.is_none() => row = Some(c.to_digit(10).unwrap() as u8),
+                _ => return Err(SanError::Invalid),
+            }
+        }
+
+        let candidates: Vec<Move> = self
+            .legal_moves()
+            .into_iter()
+            .filter(|m| {
+                m.end == end
+                    && san_letter(m.piece) == letter
+                    && column.is_none_or(|c| m.start.column == c)
+                    && row.is_none_or(|r| m.start.row == r)
+                    && match (m.promotion, promotion) {
+                        (Some(piece), Some(p)) => san_letter(piece) == Some(p),
+                        (Some(piece), None) => san_letter(piece) == Some('Q'),
+                        (None, None) => true,
+                        (None, Some(_)) => false,
+                    }
+            })
+            .collect();
+
+        match candidates.len() {
+            0 => Err(SanError::Illegal),
+            1 => Ok(candidates[0]),
+            _ => Err(SanError::Ambiguous),
+        }
+    }
+
+    fn find_castle(&self, column: Column) -> Result<Move, SanError> {
+        let row = match self.to_move {
+            Color::White => 1,
+            Color::Black => 8,
+        };
+
+        self.legal_moves()
+            .into_iter()
+            .find(|m| m.is_castle() && m.end == Position::new(column, row))
+            .ok_or(SanError::Illegal)
+    }
+
+    // Adds the file, then the rank, then both, until no other legal move of
+    // the same piece to the same square shares them.
+    fn disambiguation(&self, m: &Move) -> String {
+        let others: Vec<Position> = self
+            .legal_moves()
+            .into_iter()
+            .filter(|o| o.piece == m.piece && o.end == m.end && o.start != m.start)
+            .map(|o| o.start)
+            .collect();
+
+        if others.is_empty() {
+            String::new()
+        } else if others.iter().all(|p| p.column != m.start.column) {
+            m.start.column.to_string()
+        } else if others.iter().all(|p| p.row != m.start.row) {
+            m.start.row.to_string()
+        } else {
+            m.start.to_string()
+        }
+    }
+}
+
+fn san_letter(piece: Piece) -> Option<char> {
+    match piece {
+        Piece::WhiteKing | Piece::BlackKing => Some('K'),
+        Piece::WhiteQueen | Piece::BlackQueen => Some('Q'),
+        Piece::WhiteRook | Piece::BlackRook => Some('R'),
+        Piece::WhiteBishop | Piece::BlackBishop => Some('B'),
+        Piece::WhiteKnight | Piece::BlackKnight => Some('N'),
+        Piece::WhitePawn | Piece::BlackPawn | Piece::Empty => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(board: &mut Board, moves: &[&str]) {
+        for san in moves {
+            let m = board.parse_san_move(san).unwrap();
+            board.make_move(m);
+        }
+    }
+
+    #[test]
+    fn format_simple_moves() {
+        let board = Board::default();
+        let e4 = Move::new("e2".into(), "e4".into(), Piece::WhitePawn);
+        let nf3 = Move::new("g1".into(), "f3".into(), Piece::WhiteKnight);
+
+        assert_eq!(board.move_to_san(&e4), "e4");
+        assert_eq!(board.move_to_san(&nf3), "Nf3");
+    }
+
+    #[test]
+    fn format_captures_and_checks() {
+        let mut board = Board::default();
+        play(&mut board, &["e4", "d5"]);
+        let exd5 = Move::new("e4".into(), "d5".into(), Piece::WhitePawn);
+        assert_eq!(board.move_to_san(&exd5), "exd5");
+
+        let mut board = Board::default();
+        play(
+            &mut board,
+            &["e4", "e5", "Nf3", "Nc6", "Bb5", "f5", "Nxe5", "Nd4"],
+        );
+        let m = Move::new("e5".into(), "g6".into(), Piece::WhiteKnight);
+        assert_eq!(board.move_to_san(&m), "Ng6");
+        let m = Move::new("d1".into(), "h5".into(), Piece::WhiteQueen);
+        assert_eq!(board.move_to_san(&m), "Qh5+");
+
+        let mut board = Board::default();
+        play(&mut board, &["f3", "e5", "g4"]);
+        let m = Move::new("d8".into(), "h4".into(), Piece::BlackQueen);
+        assert_eq!(board.move_to_san(&m), "Qh4#");
+    }
+
+    #[test]
+    fn format_disambiguation() {
+        let mut board = Board::empty();
+        board.add_piece(Piece::WhiteRook, "a1");
+        board.add_piece(Piece::WhiteRook, "h1");
+        let m = Move::new("a1".into(), "d1".into(), Piece::WhiteRook);
+        assert_eq!(board.move_to_san(&m), "Rad1");
+
+        let mut board = Board::empty();
+        board.add_piece(Piece::WhiteKnight, "b1");
+        board.add_piece(Piece::WhiteKnight, "b3");
+        let m = Move::new("b1".into(), "d2".into(), Piece::WhiteKnight);
+        assert_eq!(board.move_to_san(&m), "N1d2");
+
+        let mut board = Board::empty();
+        board.add_piece(Piece::WhiteQueen, "h4");
+        board.add_piece(Piece::WhiteQueen, "e4");
+        board.add_piece(Piece::WhiteQueen, "h1");
+        let m = Move::new("h4".into(), "e1".into(), Piece::WhiteQueen);
+        assert_eq!(board.move_to_san(&m), "Qh4e1");
+    }
+
+    #[test]
+    fn format_special_moves() {
+        let mut board = Board::default();
+        play(&mut board, &["e4", "Nf6", "e5", "d5"]);
+        let m = Move::new("e5".into(), "d6".into(), Piece::WhitePawn);
+        assert_eq!(board.move_to_san(&m), "exd6");
+
+        let mut board = Board::empty();
+        board.add_piece(Piece::WhiteKing, "e1");
+        board.add_piece(Piece::WhiteRook, "a1");
+        board.add_piece(Piece::WhiteRook, "h1");
+        board.add_piece(Piece::BlackKing, "d8");
+        let m = Move::new("e1".into(), "g1".into(), Piece::WhiteKing);
+        assert_eq!(board.move_to_san(&m), "O-O");
+        let m = Move::new("e1".into(), "c1".into(), Piece::WhiteKing);
+        assert_eq!(board.move_to_san(&m), "O-O-O+");
+
+        let mut board = Board::empty();
+        board.add_piece(Piece::WhitePawn, "e7");
+        board.add_piece(Piece::WhiteKing, "a6");
+        board.add_piece(Piece::BlackKing, "a8");
+        let m = Move::promote(
+            "e7".into(),
+            "e8".into(),
+            Piece::WhitePawn,
+            Piece::WhiteQueen,
+        );
+        assert_eq!(board.move_to_san(&m), "e8=Q#");
+        let m = Move::promote(
+            "e7".into(),
+            "e8".into(),
+            Piece::WhitePawn,
+            Piece::WhiteKnight,
+        );
+        assert_eq!(board.move_to_san(&m), "e8=N");
+    }
+
+    #[test]
+    fn parse_moves() {
+        let board = Board::default();
+        assert_eq!(
+            board.parse_san_move("Nf3"),
+            Ok(Move::new("g1".into(), "f3".into(), Piece::WhiteKnight))
+        );
+        assert_eq!(
+            board.parse_san_move("e4"),
+            Ok(Move::new("e2".into(), "e4".into(), Piece::WhitePawn))
+        );
+        assert_eq!(
+            board.parse_san_move(" Ng1-f3!? "),
+            Ok(Move::new("g1".into(), "f3".into(), Piece::WhiteKnight))
+        );
+    }
+
+    #[test]
+    fn parse_special_moves() {
+        let mut board = Board::default();
+        play(&mut board, &["e4", "Nf6", "e5", "d5"]);
+        assert_eq!(
+            board.parse_san_move("exd6 e.p."),
+            Ok(Move::new("e5".into(), "d6".into(), Piece::WhitePawn))
+        );
+
+        let mut board = Board::empty();
+        board.add_piece(Piece::BlackKing, "e8");
+        board.add_piece(Piece::BlackRook, "a8");
+        board.to_move = Color::Black;
+        let castle = Move::new("e8".into(), "c8".into(), Piece::BlackKing);
+        assert_eq!(board.parse_san_move("O-O-O"), Ok(castle));
+        assert_eq!(board.parse_san_move("0-0-0+"), Ok(castle));
+        assert_eq!(board.parse_san_move("O-O"), Err(SanError::Illegal));
+
+        let mut board = Board::empty();
+        board.add_piece(Piece::WhitePawn, "e7");
+        board.add_piece(Piece::WhiteKing, "a6");
+        board.add_piece(Piece::BlackKing, "a8");
+        let queen = Move::promote(
+            "e7".into(),
+            "e8".into(),
+            Piece::WhitePawn,
+            Piece::WhiteQueen,
+        );
+        let rook = Move::promote("e7".into(), "e8".into(), Piece::WhitePawn, Piece::WhiteRook);
+        assert_eq!(board.parse_san_move("e8=Q#"), Ok(queen));
+        assert_eq!(board.parse_san_move("e8Q"), Ok(queen));
+        assert_eq!(board.parse_san_move("e8"), Ok(queen));
+        assert_eq!(board.parse_san_move("e8=r"), Ok(rook));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let mut board = Board::empty();
+        board.add_piece(Piece::WhiteKnight, "b1");
+        board.add_piece(Piece::WhiteKnight, "f1");
+
+        assert_eq!(board.parse_san_move("Nd2"), Err(SanError::Ambiguous));
+        assert!(board.parse_san_move("Nbd2").is_ok());
+        assert_eq!(board.parse_san_move("Nd3"), Err(SanError::Illegal));
+        assert_eq!(board.parse_san_move("Bd3"), Err(SanError::Illegal));
+        assert_eq!(board.parse_san_move("Nz9"), Err(SanError::Invalid));
+        assert_eq!(board.parse_san_move(""), Err(SanError::Invalid));
+        assert_eq!(board.parse_san_move("hello"), Err(SanError::Invalid));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut board = Board::default();
+        play(&mut board, &["e4", "e5", "Nf3", "Nc6", "Bc4", "Nf6"]);
+
+        for m in board.legal_moves() {
+            let san = board.move_to_san(&m);
+            assert_eq!(board.parse_san_move(&san), Ok(m), "{}", san);
+        }
+    }
+}
@@ -0,0 +1,175 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use board::Color;
+
+/// The deepest the search will iterate to when nothing else stops it.
+pub const MAX_DEPTH: u32 = 64;
+
+/// When to stop searching, following the options of the UCI `go` command.
+/// Everything left as `None` is unlimited; a default `SearchLimits` runs
+/// until `MAX_DEPTH` or until stopped.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    /// Exactly how long to think.
+    pub movetime: Option<Duration>,
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+    /// Moves until the next time control, if there is one.
+    pub movestogo: Option<u32>,
+    /// Ignore the clock and search until stopped.
+    pub infinite: bool,
+    /// Look for a mate in this many moves.
+    pub mate: Option<u32>,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u32) -> SearchLimits {
+        SearchLimits {
+            depth: Some(depth),
+            ..SearchLimits::default()
+        }
+    }
+
+    pub fn movetime(movetime: Duration) -> SearchLimits {
+        SearchLimits {
+            movetime: Some(movetime),
+            ..SearchLimits::default()
+        }
+    }
+
+    /// The last depth to iterate to, taking a mate search into account.
+    pub fn max_depth(&self) -> u32 {
+        let mut depth = self.depth.unwrap_or(MAX_DEPTH);
+        if let Some(moves) = self.mate {
+            depth = depth.min(2 * moves.max(1) - 1);
+        }
+
+        depth.clamp(1, MAX_DEPTH)
+    }
+
+    /// How long `color` should spend on this move, or `None` to take as
+    /// long as the other limits allow. A share of the remaining clock is
+    /// spent along with most of the increment, but never so much that the
+    /// clock could run out.
+    pub fn time_budget(&self, color: Color) -> Option<Duration> {
+        if self.infinite {
+            return None;
+        }
+
+        if self.movetime.is_some() {
+            return self.movetime;
+        }
+
+        let (time, increment) = match color {
+            Color::White => (self.wtime?, self.winc.unwrap_or_default()),
+            Color::Black => (self.btime?, self.binc.unwrap_or_default()),
+        };
+
+        let moves = self.movestogo.unwrap_or(30).max(1);
+        let budget = time / moves + increment * 3 / 4;
+
+        Some(budget.min(time * 4 / 5))
+    }
+}
+
+/// A shared flag that asks a running search to stop as soon as it can. It
+/// can be cloned and set from any thread.
+#[derive(Debug, Clone, Default)]
+pub struct StopFlag(Arc<AtomicBool>);
+
+impl StopFlag {
+    pub fn new() -> StopFlag {
+        StopFlag::default()
+    }
+
+    pub fn stop(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn depths() {
+        assert_eq!(SearchLimits::default().max_depth(), MAX_DEPTH);
+        assert_eq!(SearchLimits::depth(5).max_depth(), 5);
+        assert_eq!(SearchLimits::depth(0).max_depth(), 1);
+
+        let limits = SearchLimits {
+            mate: Some(2),
+            ..SearchLimits::default()
+        };
+        assert_eq!(limits.max_depth(), 3);
+    }
+
+    #[test]
+    fn time_budgets() {
+        assert_eq!(SearchLimits::default().time_budget(Color::White), None);
+
+        let limits = SearchLimits::movetime(Duration::from_millis(250));
+        assert_eq!(
+            limits.time_budget(Color::Black),
+            Some(Duration::from_millis(250))
+        );
+
+        let limits = SearchLimits {
+            wtime: Some(Duration::from_secs(60)),
+            btime: Some(Duration::from_secs(30)),
+            binc: Some(Duration::from_secs(2)),
+            ..SearchLimits::default()
+        };
+        assert_eq!(
+            limits.time_budget(Color::White),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(
+            limits.time_budget(Color::Black),
+            Some(Duration::from_millis(2500))
+        );
+
+        let limits = SearchLimits {
+            wtime: Some(Duration::from_secs(1)),
+            movestogo: Some(1),
+            ..SearchLimits::default()
+        };
+        assert_eq!(
+            limits.time_budget(Color::White),
+            Some(Duration::from_millis(800))
+        );
+
+        let limits = SearchLimits {
+            infinite: true,
+            ..limits
+        };
+        assert_eq!(limits.time_budget(Color::White), None);
+    }
+
+    #[test]
+    fn stop_flag() {
+        let flag = StopFlag::new();
+        let other = flag.clone();
+        assert!(!flag.is_stopped());
+
+        other.stop();
+        assert!(flag.is_stopped());
+
+        flag.reset();
+        assert!(!other.is_stopped());
+    }
+}
@@ -0,0 +1,1293 @@
+use std::time::{Duration, Instant};
+
+use board::{Board, Color, PieceKind};
+use eval::{Evaluate, Evaluator};
+use movement::Move;
+
+mod limits;
+mod options;
+mod ordering;
+mod tt;
+
+pub use self::limits::{SearchLimits, StopFlag, MAX_DEPTH};
+pub use self::options::SearchOptions;
+use self::ordering::mvv_lva;
+pub use self::ordering::{MoveOrdering, MovePicker};
+pub use self::tt::{Bound, Entry, TranspositionTable};
+
+/// The score for delivering mate right now. Mates further away score one
+/// less for each ply, so the search prefers the quickest.
+pub const MATE: i32 = 30_000;
+/// Scores beyond this in either direction are mates.
+pub const MATE_BOUND: i32 = MATE - 1000;
+const INFINITY: i32 = MATE + 1;
+// Quiescence gives up and takes the static evaluation this far from the
+// root, in case a long run of checks and evasions never settles. Killer
+// moves are kept for each ply up to here.
+const MAX_PLY: i32 = 128;
+
+// Tuning for the selective search. Margins are in centipawns per ply of
+// remaining depth.
+const ASPIRATION_WINDOW: i32 = 50;
+const REVERSE_FUTILITY_DEPTH: u32 = 6;
+const REVERSE_FUTILITY_MARGIN: i32 = 120;
+const FUTILITY_DEPTH: u32 = 3;
+const FUTILITY_MARGIN: i32 = 150;
+const NULL_MOVE_DEPTH: u32 = 3;
+const LMR_DEPTH: u32 = 3;
+const LMR_MOVES: u32 = 3;
+
+/// Moves to mate for a mate score: positive when the side to move is
+/// mating, negative when it is being mated.
+pub fn mate_in(score: i32) -> Option<i32> {
+    if score > MATE_BOUND {
+        Some((MATE - score + 1) / 2)
+    } else if score < -MATE_BOUND {
+        Some(-(MATE + score) / 2)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    /// `None` when there are no legal moves.
+    pub best_move: Option<Move>,
+    /// Centipawns, or a mate score, for the side to move.
+    pub score: i32,
+    /// The expected line of play starting with `best_move`.
+    pub pv: Vec<Move>,
+    pub nodes: u64,
+}
+
+impl SearchResult {
+    pub fn mate_in(&self) -> Option<i32> {
+        mate_in(self.score)
+    }
+}
+
+/// Progress reported after each completed iteration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchInfo {
+    pub depth: u32,
+    pub score: i32,
+    pub nodes: u64,
+    /// Nodes per second so far.
+    pub nps: u64,
+    pub time: Duration,
+    /// How full the transposition table is, in parts per thousand.
+    pub hashfull: u32,
+    pub pv: Vec<Move>,
+}
+
+/// Searches positions, scoring the leaves with an `E`, by default the
+/// built-in `Evaluator`.
+#[derive(Debug, Default)]
+pub struct Searcher<E = Evaluator> {
+    evaluator: E,
+    nodes: u64,
+    // Hashes of the positions leading to the one being searched, oldest
+    // first, for spotting repetitions.
+    hashes: Vec<u64>,
+    // Plies since the last capture or pawn move at the root.
+    halfmove_clock: u32,
+    stop: StopFlag,
+    node_limit: Option<u64>,
+    deadline: Option<Instant>,
+    // Set once a limit is hit, after which every node returns at once and
+    // the unfinished iteration is thrown away.
+    aborted: bool,
+    tt: TranspositionTable,
+    ordering: MoveOrdering,
+    // The moves from the root to the node being searched, so each node
+    // knows which move it is replying to, with `None` for a null move.
+    path: Vec<Option<Move>>,
+    options: SearchOptions,
+}
+
+impl Searcher {
+    /// A searcher with the default 16 MB transposition table.
+    pub fn new() -> Searcher {
+        Searcher::default()
+    }
+
+    pub fn with_hash_size(megabytes: usize) -> Searcher {
+        let mut searcher = Searcher::new();
+        searcher.resize_hash(megabytes);
+        searcher
+    }
+}
+
+impl<E: Evaluate> Searcher<E> {
+    pub fn with_evaluator(evaluator: E) -> Searcher<E> {
+        Searcher {
+            evaluator,
+            nodes: 0,
+            hashes: Vec::new(),
+            halfmove_clock: 0,
+            stop: StopFlag::new(),
+            node_limit: None,
+            deadline: None,
+            aborted: false,
+            tt: TranspositionTable::default(),
+            ordering: MoveOrdering::default(),
+            path: Vec::new(),
+            options: SearchOptions::default(),
+        }
+    }
+
+    pub fn evaluator(&self) -> &E {
+        &self.evaluator
+    }
+
+    pub fn evaluator_mut(&mut self) -> &mut E {
+        &mut self.evaluator
+    }
+
+    /// Replaces the transposition table with an empty one of about
+    /// `megabytes`.
+    pub fn resize_hash(&mut self, megabytes: usize) {
+        self.tt = TranspositionTable::new(megabytes);
+    }
+
+    /// Forgets everything learned in earlier searches, as when starting a
+    /// new game.
+    pub fn clear_hash(&mut self) {
+        self.tt.clear();
+        self.ordering = MoveOrdering::default();
+    }
+
+    /// Tells the searcher about the game so far: the hashes of the
+    /// positions before the one it will search, oldest first, and the
+    /// number of plies since the last capture or pawn move.
+    pub fn set_history(&mut self, hashes: &[u64], halfmove_clock: u32) {
+        self.hashes = hashes.to_vec();
+        self.halfmove_clock = halfmove_clock;
+    }
+
+    pub fn options(&self) -> SearchOptions {
+        self.options
+    }
+
+    pub fn set_options(&mut self, options: SearchOptions) {
+        self.options = options;
+    }
+
+    /// A handle for stopping the search from another thread. `go` leaves
+    /// the flag alone, so whoever starts a search resets it first, as a
+    /// UCI loop does on receiving `go`: a stop that comes in before the
+    /// search gets going is then still obeyed, and one left over from the
+    /// last search is not.
+    pub fn stop_flag(&self) -> StopFlag {
+        self.stop.clone()
+    }
+
+    /// Searches `depth` plies ahead. Positions repeated within the search
+    /// or from the game history, and those reached after fifty moves
+    /// without a capture or pawn move, count as draws.
+    pub fn search(&mut self, board: &Board, depth: u32) -> SearchResult {
+        self.go(board, &SearchLimits::depth(depth), |_| {})
+    }
+
+    /// Searches one ply deeper at a time until `limits` or the stop flag
+    /// end it, calling `info` after each depth is finished. The result is
+    /// from the last complete iteration; if not even the first one
+    /// finished, it is simply the first legal move.
+    pub fn go<F: FnMut(&SearchInfo)>(
+        &mut self,
+        board: &Board,
+        limits: &SearchLimits,
+        mut info: F,
+    ) -> SearchResult {
+        let start = Instant::now();
+        let budget = limits.time_budget(board.to_move);
+
+        self.nodes = 0;
+        self.aborted = false;
+        self.node_limit = limits.nodes;
+        self.deadline = budget.map(|budget| start + budget);
+        self.tt.new_search();
+        self.ordering.age();
+        self.path.clear();
+        self.evaluator.reset(board);
+
+        let mut result: Option<SearchResult> = None;
+
+        for depth in 1..=limits.max_depth() {
+            let mut pv = Vec::new();
+            let guess = result.as_ref().map(|result| result.score);
+            let score = self.search_root(board, depth, guess, &mut pv);
+
+            if self.aborted {
+                break;
+            }
+
+            self.extend_pv(board, &mut pv, depth);
+            let time = start.elapsed();
+            info(&SearchInfo {
+                depth,
+                score,
+                nodes: self.nodes,
+                nps: (self.nodes as f64 / time.as_secs_f64().max(0.001)) as u64,
+                time,
+                hashfull: self.tt.hashfull(),
+                pv: pv.clone(),
+            });
+
+            let finished = pv.is_empty()
+                || limits
+                    .mate
+                    .is_some_and(|moves| mate_in(score).is_some_and(|n| n > 0 && n <= moves as i32))
+                // Another iteration would most likely run out of time.
+                || budget.is_some_and(|budget| time >= budget / 2);
+
+            result = Some(SearchResult {
+                best_move: pv.first().cloned(),
+                score,
+                pv,
+                nodes: self.nodes,
+            });
+
+            if finished {
+                break;
+            }
+        }
+
+        let mut result = result.unwrap_or_else(|| {
+            let best_move = board.legal_moves().first().cloned();
+            SearchResult {
+                best_move,
+                score: 0,
+                pv: best_move.into_iter().collect(),
+                nodes: 0,
+            }
+        });
+        result.nodes = self.nodes;
+
+        result
+    }
+
+    // Searches the root with a window around the score from the last
+    // iteration, which cuts off more, widening the window whenever the
+    // score falls outside it.
+    fn search_root(
+        &mut self,
+        board: &Board,
+        depth: u32,
+        guess: Option<i32>,
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        let mut delta = ASPIRATION_WINDOW;
+        let (mut alpha, mut beta) = match guess {
+            Some(guess)
+                if self.options.aspiration_windows && depth >= 4 && guess.abs() < MATE_BOUND =>
+            {
+                (guess - delta, guess + delta)
+            }
+            _ => (-INFINITY, INFINITY),
+        };
+
+        loop {
+            pv.clear();
+            let score = self.negamax(board, depth, 0, alpha, beta, self.halfmove_clock, pv);
+
+            if self.aborted {
+                return score;
+            } else if score <= alpha {
+                alpha = (score - delta).max(-INFINITY);
+            } else if score >= beta {
+                beta = (score + delta).min(INFINITY);
+            } else {
+                return score;
+            }
+            delta *= 4;
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn negamax(
+        &mut self,
+        board: &Board,
+        depth: u32,
+        ply: i32,
+        mut alpha: i32,
+        beta: i32,
+        halfmoves: u32,
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        if self.out_of_time_or_nodes() {
+            self.aborted = true;
+            return 0;
+        }
+        self.nodes += 1;
+
+        let hash = board.hash();
+        if ply > 0 && self.is_repetition(hash, halfmoves) {
+            return 0;
+        }
+
+        let entry = self.tt.probe(hash);
+        if let Some(entry) = entry {
+            if ply > 0 && entry.depth as u32 >= depth {
+                let score = entry.score(ply);
+                let usable = match entry.bound {
+                    Bound::Exact => true,
+                    Bound::Lower => score >= beta,
+                    Bound::Upper => score <= alpha,
+                };
+                if usable {
+                    return score;
+                }
+            }
+        }
+
+        // Being mated on the hundredth ply still loses.
+        if ply > 0 && halfmoves >= 100 {
+            return if board.checkmate(board.to_move) {
+                -MATE + ply
+            } else {
+                0
+            };
+        }
+
+        // Quiescence only looks at captures unless in check, so it can't
+        // tell a stalemate apart.
+        if depth == 0 {
+            if !board.in_check(board.to_move) && !board.has_legal_move() {
+                return 0;
+            }
+            return self.quiescence(board, ply, alpha, beta);
+        }
+
+        let options = self.options;
+        let in_check = board.in_check(board.to_move);
+        let pv_node = beta - alpha > 1;
+
+        // Nothing is pruned in check or along the principal variation, so
+        // the static evaluation is only needed elsewhere.
+        let eval = if in_check || pv_node {
+            None
+        } else {
+            Some(self.evaluator.evaluate(board))
+        };
+
+        if let Some(eval) = eval {
+            // A stalemate is a draw however far ahead the evaluation says
+            // the side to move is, so it has to be ruled out before that
+            // is trusted to prune.
+            if !board.has_legal_move() {
+                return 0;
+            }
+
+            if options.reverse_futility
+                && depth <= REVERSE_FUTILITY_DEPTH
+                && beta.abs() < MATE_BOUND
+                && eval - REVERSE_FUTILITY_MARGIN * depth as i32 >= beta
+            {
+                return eval;
+            }
+
+            // Passing is never allowed twice in a row, and not without
+            // pieces, since in pawn endings being forced to move is often
+            // what loses.
+            if options.null_move
+                && depth >= NULL_MOVE_DEPTH
+                && eval >= beta
+                && beta.abs() < MATE_BOUND
+                && self.path.last() != Some(&None)
+                && has_pieces(board, board.to_move)
+            {
+                let reduction = 3 + depth / 6;
+                let child = pass(board);
+
+                self.hashes.push(hash);
+                self.path.push(None);
+                self.evaluator.make_move(board, None);
+                let score = -self.negamax(
+                    &child,
+                    depth.saturating_sub(reduction + 1),
+                    ply + 1,
+                    -beta,
+                    -beta + 1,
+                    0,
+                    &mut Vec::new(),
+                );
+                self.evaluator.unmake_move();
+                self.path.pop();
+                self.hashes.pop();
+
+                if self.aborted {
+                    return 0;
+                }
+
+                // A mate found after passing isn't a real mate.
+                if score >= beta {
+                    return if score > MATE_BOUND { beta } else { score };
+                }
+            }
+        }
+
+        let futile = options.futility
+            && depth <= FUTILITY_DEPTH
+            && alpha.abs() < MATE_BOUND
+            && eval.is_some_and(|eval| eval + FUTILITY_MARGIN * depth as i32 <= alpha);
+
+        let previous = self.path.last().cloned().flatten();
+        let mut picker = MovePicker::new(
+            entry.and_then(|entry| entry.best_move),
+            &self.ordering,
+            ply,
+            previous,
+        );
+
+        self.hashes.push(hash);
+        let original_alpha = alpha;
+        let mut best = -INFINITY;
+        let mut best_move = None;
+        let mut searched = 0;
+        let mut legal = 0;
+
+        while let Some(m) = picker.next(board, &self.ordering) {
+            legal += 1;
+            let capture = m.flags.capture;
+            let quiet = !capture && m.promotion.is_none();
+            let irreversible = m.piece.kind() == Some(PieceKind::Pawn) || capture;
+            let halfmoves = if irreversible { 0 } else { halfmoves + 1 };
+
+            let mut child = board.clone();
+            child.make_move(m);
+            let gives_check = child.in_check(child.to_move);
+
+            if futile && searched > 0 && quiet && !gives_check {
+                continue;
+            }
+
+            let extend = options.check_extensions && gives_check && ply + (depth as i32) < MAX_PLY;
+            let new_depth = if extend { depth } else { depth - 1 };
+
+            let reduction = if options.late_move_reductions
+                && !pv_node
+                && depth >= LMR_DEPTH
+                && searched >= LMR_MOVES
+                && quiet
+                && !in_check
+                && !gives_check
+            {
+                let reduction = if searched >= 2 * LMR_MOVES { 2 } else { 1 };
+                reduction.min(new_depth - 1)
+            } else {
+                0
+            };
+
+            self.path.push(Some(m));
+            self.evaluator.make_move(board, Some(m));
+            let mut line = Vec::new();
+            let score = if searched == 0 {
+                -self.negamax(
+                    &child,
+                    new_depth,
+                    ply + 1,
+                    -beta,
+                    -alpha,
+                    halfmoves,
+                    &mut line,
+                )
+            } else {
+                // Later moves are expected to fail low, which a null window
+                // proves more cheaply. Only when one doesn't is it searched
+                // again at full depth, then with the full window.
+                let lower = if options.principal_variation {
+                    alpha + 1
+                } else {
+                    beta
+                };
+
+                let mut score = -self.negamax(
+                    &child,
+                    new_depth - reduction,
+                    ply + 1,
+                    -lower,
+                    -alpha,
+                    halfmoves,
+                    &mut line,
+                );
+                if score > alpha && reduction > 0 && !self.aborted {
+                    line.clear();
+                    score = -self.negamax(
+                        &child,
+                        new_depth,
+                        ply + 1,
+                        -lower,
+                        -alpha,
+                        halfmoves,
+                        &mut line,
+                    );
+                }
+                if score > alpha && score < beta && lower < beta && !self.aborted {
+                    line.clear();
+                    score = -self.negamax(
+                        &child,
+                        new_depth,
+                        ply + 1,
+                        -beta,
+                        -alpha,
+                        halfmoves,
+                        &mut line,
+                    );
+                }
+                score
+            };
+            self.evaluator.unmake_move();
+            self.path.pop();
+            searched += 1;
+
+            if self.aborted {
+                self.hashes.pop();
+                return 0;
+            }
+
+            if score > best {
+                best = score;
+                best_move = Some(m);
+            }
+
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(m);
+                pv.extend(line);
+            }
+
+            if alpha >= beta {
+                if quiet {
+                    self.ordering.reward(m, previous, ply, depth);
+                }
+                break;
+            }
+        }
+
+        self.hashes.pop();
+
+        if legal == 0 {
+            return if in_check { -MATE + ply } else { 0 };
+        }
+
+        let bound = if best >= beta {
+            Bound::Lower
+        } else if best <= original_alpha {
+            Bound::Upper
+        } else {
+            Bound::Exact
+        };
+        self.tt.store(hash, depth, bound, best, ply, best_move);
+
+        best
+    }
+
+    // Searches captures and promotions until the position is quiet, so
+    // that the evaluation isn't taken in the middle of an exchange. The side
+    // to move may "stand pat" on the static evaluation instead of capturing,
+    // unless it is in check, when every evasion is tried.
+    fn quiescence(&mut self, board: &Board, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+        if self.out_of_time_or_nodes() {
+            self.aborted = true;
+            return 0;
+        }
+        self.nodes += 1;
+
+        let in_check = board.in_check(board.to_move);
+        if ply >= MAX_PLY {
+            return self.evaluator.evaluate(board);
+        }
+
+        let mut best = -INFINITY;
+        let moves = if in_check {
+            let evasions = board.legal_moves();
+            if evasions.is_empty() {
+                return -MATE + ply;
+            }
+            evasions
+        } else {
+            let stand_pat = self.evaluator.evaluate(board);
+            if stand_pat >= beta {
+                return stand_pat;
+            }
+            alpha = alpha.max(stand_pat);
+            best = stand_pat;
+
+            // Captures that lose material on the exchange can't do better
+            // than standing pat.
+            let mut captures: Vec<Move> = board
+                .captures_and_promotions()
+                .into_iter()
+                .filter(|m| m.promotion.is_some() || board.see_ge(m, 0))
+                .collect();
+            captures.sort_by_key(|m| -mvv_lva(board, m));
+            captures
+        };
+
+        for m in moves {
+            let mut child = board.clone();
+            child.make_move(m);
+
+            self.evaluator.make_move(board, Some(m));
+            let score = -self.quiescence(&child, ply + 1, -beta, -alpha);
+            self.evaluator.unmake_move();
+            if self.aborted {
+                return 0;
+            }
+
+            best = best.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        best
+    }
+
+    // Lines cut short by hash table hits are filled out with the stored
+    // best moves, up to the depth searched.
+    fn extend_pv(&self, board: &Board, pv: &mut Vec<Move>, depth: u32) {
+        let mut board = board.clone();
+        for m in pv.iter() {
+            board.make_move(*m);
+        }
+
+        while (pv.len() as u32) < depth {
+            let next = self
+                .tt
+                .probe(board.hash())
+                .and_then(|entry| entry.best_move)
+                .filter(|m| board.is_legal(m));
+
+            match next {
+                Some(m) => {
+                    pv.push(m);
+                    board.make_move(m);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn out_of_time_or_nodes(&self) -> bool {
+        self.aborted
+            || self.stop.is_stopped()
+            || self.node_limit.is_some_and(|limit| self.nodes >= limit)
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    // Only positions with the same side to move since the last capture or
+    // pawn move can repeat this one.
+    fn is_repetition(&self, hash: u64, halfmoves: u32) -> bool {
+        self.hashes
+            .iter()
+            .rev()
+            .take(halfmoves as usize)
+            .skip(1)
+            .step_by(2)
+            .any(|&earlier| earlier == hash)
+    }
+}
+
+// The same position with the other side to move, for null move pruning.
+fn pass(board: &Board) -> Board {
+    let mut board = board.clone();
+    board.to_move = board.to_move.opposite();
+    board.set_en_passant(None);
+    board
+}
+
+fn has_pieces(board: &Board, color: Color) -> bool {
+    board
+        .pieces(color)
+        .iter()
+        .any(|&(_, piece)| !matches!(piece.kind(), Some(PieceKind::Pawn | PieceKind::King)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::Color;
+    use eval::Material;
+
+    #[test]
+    fn mate_in_one() {
+        let board = board!(
+            "
+            . . . . . . k .
+            . . . . . p p p
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            R . . . . . K .
+            "
+        );
+
+        let result = Searcher::new().search(&board, 2);
+        assert_eq!(result.score, MATE - 1);
+        assert_eq!(result.mate_in(), Some(1));
+        assert_eq!(board.move_to_san(&result.best_move.unwrap()), "Ra8#");
+        assert_eq!(result.pv.len(), 1);
+    }
+
+    #[test]
+    fn mate_in_two() {
+        let board = board!(
+            "
+            . . . . . . . k
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . R . . . . . .
+            R . . . K . . .
+            "
+        );
+
+        let result = Searcher::new().search(&board, 3);
+        assert_eq!(result.mate_in(), Some(2));
+        assert_eq!(result.pv.len(), 3);
+
+        let mut after = board.clone();
+        after.make_move(result.pv[0]);
+        after.make_move(result.pv[1]);
+        after.make_move(result.pv[2]);
+        assert!(after.checkmate(Color::Black));
+    }
+
+    #[test]
+    fn mated_and_stalemated() {
+        let board = board!(
+            "
+            k . . . . . . .
+            . Q . . . . . .
+            . K . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            Black to move
+            "
+        );
+
+        let result = Searcher::new().search(&board, 1);
+        assert_eq!(result.best_move, None);
+        assert_eq!(result.score, -MATE);
+        assert_eq!(result.mate_in(), Some(0));
+
+        let board = board!(
+            "
+            k . . . . . . .
+            . . Q . . . . .
+            . K . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            Black to move
+            "
+        );
+
+        let result = Searcher::new().search(&board, 1);
+        assert_eq!(result.best_move, None);
+        assert_eq!(result.score, 0);
+    }
+
+    #[test]
+    fn stalemate_with_material_ahead() {
+        let board = board!(
+            "
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            p . p . . . . p
+            P . P . . k . P
+            R B . . . . . K
+            "
+        );
+
+        // A zero window well below the material, as away from the
+        // principal variation, where the evaluation alone would prune.
+        let mut searcher = Searcher::with_evaluator(Material);
+        for depth in 0..3 {
+            assert_eq!(
+                searcher.negamax(&board, depth, 1, 99, 100, 0, &mut Vec::new()),
+                0
+            );
+        }
+    }
+
+    #[test]
+    fn wins_material() {
+        let board = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . q . . . .
+            . . . . . . . .
+            . . N . . . . .
+            . . . . . . . .
+            . . . . K . . .
+            "
+        );
+
+        let result = Searcher::with_evaluator(Material).search(&board, 2);
+        assert_eq!(board.move_to_san(&result.best_move.unwrap()), "Nxd5");
+        assert_eq!(result.score, 300);
+    }
+
+    #[test]
+    fn quiescence() {
+        let board = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . p . . .
+            . . . p . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . Q K n . .
+            "
+        );
+
+        // Standing pat beats taking the defended pawn, but the knight is
+        // free.
+        let mut searcher = Searcher::with_evaluator(Material);
+        assert_eq!(
+            searcher.quiescence(&board, 0, -INFINITY, INFINITY),
+            900 - 500 + 300
+        );
+
+        let result = searcher.search(&board, 1);
+        assert_eq!(result.score, 700);
+        assert_ne!(board.move_to_san(&result.best_move.unwrap()), "Qxd5");
+    }
+
+    #[test]
+    fn quiescence_in_check() {
+        let board = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . q .
+            . . . . . . . K
+            "
+        );
+
+        let mut searcher = Searcher::with_evaluator(Material);
+        assert_eq!(searcher.quiescence(&board, 0, -INFINITY, INFINITY), 0);
+
+        let board = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . b . .
+            . . . . . . q .
+            . . . . . . . K
+            "
+        );
+        assert_eq!(
+            searcher.quiescence(&board, 3, -INFINITY, INFINITY),
+            -MATE + 3
+        );
+    }
+
+    #[test]
+    fn fifty_move_rule() {
+        let board = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . Q K . . .
+            "
+        );
+
+        assert_eq!(
+            Searcher::with_evaluator(Material).search(&board, 2).score,
+            900
+        );
+
+        let mut searcher = Searcher::with_evaluator(Material);
+        searcher.set_history(&[], 99);
+        assert_eq!(searcher.search(&board, 2).score, 0);
+    }
+
+    #[test]
+    fn repetition() {
+        // White's king only has a2, where it has already been.
+        let board = board!(
+            "
+            . r . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . p
+            . . . . . . . P
+            . . k . . . . .
+            . . . . . . . .
+            K . . . . . . .
+            "
+        );
+
+        let result = Searcher::with_evaluator(Material).search(&board, 1);
+        assert_eq!(result.score, -500);
+
+        let mut game = board.clone();
+        let mut hashes = Vec::new();
+        for san in &["Ka2", "Rb7", "Ka1", "Rb8"] {
+            hashes.push(game.hash());
+            let m = game.parse_san_move(san).unwrap();
+            game.make_move(m);
+        }
+
+        let mut searcher = Searcher::with_evaluator(Material);
+        searcher.set_history(&hashes, 4);
+        let result = searcher.search(&board, 1);
+        assert_eq!(board.move_to_san(&result.best_move.unwrap()), "Ka2");
+        assert_eq!(result.score, 0);
+    }
+
+    #[test]
+    fn iterative_deepening() {
+        let mut depths = Vec::new();
+        let result = Searcher::new().go(&Board::default(), &SearchLimits::depth(3), |info| {
+            assert_eq!(info.pv.len() as u32, info.depth);
+            depths.push(info.depth);
+        });
+
+        assert_eq!(depths, vec![1, 2, 3]);
+        assert_eq!(result.pv.len(), 3);
+        assert!(result.nodes > 20);
+    }
+
+    #[test]
+    fn hash_table_saves_work() {
+        let board = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . Q K . . .
+            "
+        );
+
+        let mut searcher = Searcher::new();
+        let first = searcher.search(&board, 3);
+        let second = searcher.search(&board, 3);
+        assert_eq!(first.score, second.score);
+        assert!(second.nodes < first.nodes);
+
+        searcher.clear_hash();
+        assert_eq!(searcher.search(&board, 3).nodes, first.nodes);
+
+        let mut hashfull = 0;
+        let mut tiny = Searcher::with_hash_size(0);
+        tiny.go(&board, &SearchLimits::depth(2), |info| {
+            hashfull = info.hashfull
+        });
+        assert_eq!(hashfull, 1000);
+    }
+
+    #[test]
+    fn node_limit() {
+        let limits = SearchLimits {
+            nodes: Some(500),
+            ..SearchLimits::default()
+        };
+
+        let mut depths = Vec::new();
+        let result = Searcher::new().go(&Board::default(), &limits, |info| depths.push(info.depth));
+        assert!(result.best_move.is_some());
+        assert!(result.nodes <= 500);
+        assert_eq!(depths, vec![1, 2]);
+
+        let limits = SearchLimits {
+            nodes: Some(1),
+            ..SearchLimits::default()
+        };
+        let result = Searcher::new().go(&Board::default(), &limits, |_| {});
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    fn mate_search_stops_early() {
+        let board = board!(
+            "
+            . . . . . . k .
+            . . . . . p p p
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            R . . . . . K .
+            "
+        );
+        let limits = SearchLimits {
+            mate: Some(3),
+            ..SearchLimits::default()
+        };
+
+        let mut depths = Vec::new();
+        let result = Searcher::new().go(&board, &limits, |info| depths.push(info.depth));
+        assert_eq!(result.mate_in(), Some(1));
+        assert_eq!(depths, vec![1]);
+    }
+
+    #[test]
+    fn stopping_from_another_thread() {
+        let mut searcher = Searcher::new();
+        let stop = searcher.stop_flag();
+        let limits = SearchLimits {
+            infinite: true,
+            ..SearchLimits::default()
+        };
+
+        let stopper = ::std::thread::spawn(move || {
+            ::std::thread::sleep(Duration::from_millis(100));
+            stop.stop();
+        });
+
+        let start = Instant::now();
+        let result = searcher.go(&Board::default(), &limits, |_| {});
+        stopper.join().unwrap();
+
+        assert!(result.best_move.is_some());
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn stopped_before_starting() {
+        let mut searcher = Searcher::new();
+        let stop = searcher.stop_flag();
+        let limits = SearchLimits {
+            infinite: true,
+            ..SearchLimits::default()
+        };
+
+        stop.reset();
+        stop.stop();
+        let result = searcher.go(&Board::default(), &limits, |_| {});
+        assert!(result.best_move.is_some());
+        assert!(stop.is_stopped());
+
+        stop.reset();
+        searcher.search(&Board::default(), 1);
+        stop.stop();
+
+        stop.reset();
+        let result = searcher.go(&Board::default(), &SearchLimits::depth(2), |_| {});
+        assert_eq!(result.pv.len(), 2);
+    }
+
+    #[test]
+    fn movetime() {
+        let limits = SearchLimits::movetime(Duration::from_millis(50));
+        let start = Instant::now();
+        let result = Searcher::new().go(&Board::default(), &limits, |_| {});
+
+        assert!(result.best_move.is_some());
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn each_option_can_be_turned_off() {
+        let board = board!(
+            "
+            . . . . . . . k
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . R . . . . . .
+            R . . . K . . .
+            "
+        );
+
+        let all = SearchOptions::default();
+        let options = [
+            SearchOptions::none(),
+            SearchOptions {
+                null_move: false,
+                ..all
+            },
+            SearchOptions {
+                late_move_reductions: false,
+                ..all
+            },
+            SearchOptions {
+                futility: false,
+                ..all
+            },
+            SearchOptions {
+                reverse_futility: false,
+                ..all
+            },
+            SearchOptions {
+                check_extensions: false,
+                ..all
+            },
+            SearchOptions {
+                principal_variation: false,
+                ..all
+            },
+            SearchOptions {
+                aspiration_windows: false,
+                ..all
+            },
+        ];
+
+        for &options in options.iter() {
+            let mut searcher = Searcher::new();
+            searcher.set_options(options);
+            assert_eq!(searcher.options(), options);
+            assert_eq!(searcher.search(&board, 3).mate_in(), Some(2));
+        }
+    }
+
+    #[test]
+    fn selective_search_saves_work() {
+        let board = board!(
+            "
+            r . b q k . . r
+            p p p . . p p p
+            . . n b . n . .
+            . . . p p . . .
+            . . . P P . . .
+            . . N B . N . .
+            P P P . . P P P
+            R . B Q K . . R
+            "
+        );
+
+        let mut plain = Searcher::with_evaluator(Material);
+        plain.set_options(SearchOptions::none());
+        let plain = plain.search(&board, 3);
+        let selective = Searcher::with_evaluator(Material).search(&board, 3);
+
+        assert!(selective.nodes < plain.nodes);
+    }
+
+    #[test]
+    fn null_move_needs_pieces() {
+        let mut board = board!(
+            "
+            . . . . k . . .
+            . . . p . . . .
+            . . . . . . . .
+            . . . . P . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . K . . N
+            "
+        );
+        board.to_move = Color::Black;
+        board.make_move(board.parse_san_move("d5").unwrap());
+
+        assert!(has_pieces(&board, Color::White));
+        assert!(!has_pieces(&board, Color::Black));
+
+        let passed = pass(&board);
+        assert_eq!(passed.to_move, Color::Black);
+        assert_eq!(passed.en_passant(), None);
+
+        board.set_en_passant(None);
+        assert_eq!(pass(&passed).hash(), board.hash());
+    }
+
+    // Follows the search through the hooks, checking that every position
+    // it is asked about is the one the hooks lead to.
+    #[derive(Default)]
+    struct Tracking {
+        line: Vec<Board>,
+        deepest: usize,
+    }
+
+    impl Evaluate for Tracking {
+        fn evaluate(&mut self, board: &Board) -> i32 {
+            assert_eq!(self.line.last().unwrap().hash(), board.hash());
+            Material.evaluate(board)
+        }
+
+        fn reset(&mut self, board: &Board) {
+            self.line = vec![board.clone()];
+        }
+
+        fn make_move(&mut self, board: &Board, m: Option<Move>) {
+            assert_eq!(self.line.last().unwrap().hash(), board.hash());
+            let next = match m {
+                Some(m) => {
+                    let mut next = board.clone();
+                    next.make_move(m);
+                    next
+                }
+                None => pass(board),
+            };
+            self.line.push(next);
+            self.deepest = self.deepest.max(self.line.len() - 1);
+        }
+
+        fn unmake_move(&mut self) {
+            self.line.pop();
+        }
+    }
+
+    #[test]
+    fn incremental_evaluation() {
+        let board = board!(
+            "
+            r . b q k . . r
+            p p p . . p p p
+            . . n b . n . .
+            . . . p p . . .
+            . . . P P . . .
+            . . N B . N . .
+            P P P . . P P P
+            R . B Q K . . R
+            "
+        );
+
+        let mut searcher = Searcher::with_evaluator(Tracking::default());
+        searcher.search(&board, 3);
+        assert_eq!(searcher.evaluator().line.len(), 1);
+        assert!(searcher.evaluator().deepest > 3);
+
+        let limits = SearchLimits {
+            nodes: Some(300),
+            ..SearchLimits::default()
+        };
+        searcher.go(&board, &limits, |_| {});
+        assert_eq!(searcher.evaluator().line.len(), 1);
+    }
+}
@@ -0,0 +1,56 @@
+/// Switches for the selective parts of the search, so each can be turned
+/// off to measure what it is worth. Everything is on by default; with
+/// everything off the search is plain alpha-beta.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchOptions {
+    /// Let the opponent move twice in a row, and cut off if that still
+    /// isn't enough for them. Never tried in check or with only pawns
+    /// left, where passing might be the best move.
+    pub null_move: bool,
+    /// Search quiet moves late in the move order less deeply, and only
+    /// search them fully if they turn out better than expected.
+    pub late_move_reductions: bool,
+    /// Skip quiet moves near the leaves when the static evaluation is too
+    /// far below alpha for them to matter.
+    pub futility: bool,
+    /// Cut off near the leaves when the static evaluation is so far above
+    /// beta that the opponent won't get back.
+    pub reverse_futility: bool,
+    /// Search a ply deeper after moves that give check.
+    pub check_extensions: bool,
+    /// Search moves after the first with a null window, only searching
+    /// again with the full window if they beat it.
+    pub principal_variation: bool,
+    /// Start each iteration with a narrow window around the last score,
+    /// widening it when the score falls outside.
+    pub aspiration_windows: bool,
+}
+
+impl SearchOptions {
+    /// Everything turned off.
+    pub fn none() -> SearchOptions {
+        SearchOptions {
+            null_move: false,
+            late_move_reductions: false,
+            futility: false,
+            reverse_futility: false,
+            check_extensions: false,
+            principal_variation: false,
+            aspiration_windows: false,
+        }
+    }
+}
+
+impl Default for SearchOptions {
+    fn default() -> SearchOptions {
+        SearchOptions {
+            null_move: true,
+            late_move_reductions: true,
+            futility: true,
+            reverse_futility: true,
+            check_extensions: true,
+            principal_variation: true,
+            aspiration_windows: true,
+        }
+    }
+}
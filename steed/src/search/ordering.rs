@@ -0,0 +1,348 @@
+use board::{Board, Piece};
+use movement::Move;
+use position::Position;
+use search::MAX_PLY;
+use square::Square;
+
+/// What the search has learned about good quiet moves: two killer moves
+/// per ply, a history score for each piece and destination, and the reply
+/// that last refuted each move.
+#[derive(Debug, Clone)]
+pub struct MoveOrdering {
+    killers: Vec<[Option<Move>; 2]>,
+    history: Vec<i32>,
+    countermoves: Vec<Option<Move>>,
+}
+
+impl Default for MoveOrdering {
+    fn default() -> MoveOrdering {
+        MoveOrdering {
+            killers: vec![[None; 2]; MAX_PLY as usize + 1],
+            history: vec![0; 12 * 64],
+            countermoves: vec![None; 12 * 64],
+        }
+    }
+}
+
+impl MoveOrdering {
+    pub fn killers(&self, ply: i32) -> [Option<Move>; 2] {
+        self.killers[ply as usize]
+    }
+
+    pub fn countermove(&self, previous: Option<Move>) -> Option<Move> {
+        previous.and_then(|previous| self.countermoves[slot(previous.piece, previous.end)])
+    }
+
+    pub fn history(&self, m: &Move) -> i32 {
+        self.history[slot(m.piece, m.end)]
+    }
+
+    /// Records that the quiet move `m` caused a beta cutoff at `ply`.
+    pub fn reward(&mut self, m: Move, previous: Option<Move>, ply: i32, depth: u32) {
+        let killers = &mut self.killers[ply as usize];
+        if killers[0] != Some(m) {
+            killers[1] = killers[0].replace(m);
+        }
+
+        self.history[slot(m.piece, m.end)] += (depth * depth) as i32;
+
+        if let Some(previous) = previous {
+            self.countermoves[slot(previous.piece, previous.end)] = Some(m);
+        }
+    }
+
+    /// Called between searches: killers only make sense for the position
+    /// they were found in, and old history counts for less.
+    pub fn age(&mut self) {
+        for killers in &mut self.killers {
+            *killers = [None; 2];
+        }
+        for score in &mut self.history {
+            *score /= 2;
+        }
+    }
+}
+
+fn slot(piece: Piece, end: Position) -> usize {
+    let piece = match piece.kind() {
+        Some(kind) => kind as usize + 6 * (piece.color() as usize),
+        None => 0,
+    };
+
+    piece * 64 + Square::from(end).index()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Stage {
+    HashMove,
+    GenerateCaptures,
+    GoodCaptures,
+    Refutations,
+    GenerateQuiets,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+/// Hands out moves best first, generating and sorting only as much as it
+/// needs to: the hash move, captures that don't lose material (most
+/// valuable victim, least valuable attacker), killers and the countermove,
+/// other quiet moves by history, and finally the losing captures. The hash
+/// move and the refutations are only checked for legality, so a cutoff on
+/// one of them saves generating the rest, and quiet moves aren't generated
+/// until the captures have all been tried. Every move handed out has its
+/// flags set for the position it is played in.
+pub struct MovePicker {
+    stage: Stage,
+    hash_move: Option<Move>,
+    // Killers and the countermove still to try, the next one last.
+    refutations: Vec<Move>,
+    // Moves already handed out before generation, to leave out of it.
+    played: Vec<Move>,
+    captures: Vec<(Move, i32)>,
+    bad_captures: Vec<Move>,
+    quiets: Vec<(Move, i32)>,
+    generated: usize,
+}
+
+impl MovePicker {
+    pub fn new(
+        hash_move: Option<Move>,
+        ordering: &MoveOrdering,
+        ply: i32,
+        previous: Option<Move>,
+    ) -> MovePicker {
+        let [first, second] = ordering.killers(ply);
+        let mut refutations = Vec::new();
+        for &m in [first, second, ordering.countermove(previous)]
+            .iter()
+            .flatten()
+        {
+            if !refutations.contains(&m) {
+                refutations.push(m);
+            }
+        }
+        refutations.reverse();
+
+        MovePicker {
+            stage: Stage::HashMove,
+            hash_move,
+            refutations,
+            played: Vec::new(),
+            captures: Vec::new(),
+            bad_captures: Vec::new(),
+            quiets: Vec::new(),
+            generated: 0,
+        }
+    }
+
+    /// How many moves have been generated so far. The hash move and the
+    /// refutations don't count, as they are only checked.
+    pub fn generated(&self) -> usize {
+        self.generated
+    }
+
+    pub fn next(&mut self, board: &Board, ordering: &MoveOrdering) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::HashMove => {
+                    self.stage = Stage::GenerateCaptures;
+                    if let Some(mut m) = self.hash_move.filter(|m| board.is_legal(m)) {
+                        m.flags = board.move_flags(&m);
+                        self.played.push(m);
+                        return Some(m);
+                    }
+                }
+                Stage::GenerateCaptures => {
+                    let moves = board.captures_and_promotions();
+                    self.generated += moves.len();
+                    self.captures = moves
+                        .into_iter()
+                        .filter(|m| !self.played.contains(m))
+                        .map(|m| (m, mvv_lva(board, &m)))
+                        .collect();
+                    self.stage = Stage::GoodCaptures;
+                }
+                Stage::GoodCaptures => match pop_best(&mut self.captures) {
+                    Some(m) if m.promotion.is_some() || board.see_ge(&m, 0) => return Some(m),
+                    Some(m) => self.bad_captures.push(m),
+                    None => self.stage = Stage::Refutations,
+                },
+                Stage::Refutations => match self.refutations.pop() {
+                    Some(mut m) => {
+                        // Found in other positions, so they may be captures
+                        // or not even legal here.
+                        if !self.played.contains(&m)
+                            && m.promotion.is_none()
+                            && !board.is_capture(&m)
+                            && board.is_legal(&m)
+                        {
+                            m.flags = board.move_flags(&m);
+                            self.played.push(m);
+                            return Some(m);
+                        }
+                    }
+                    None => self.stage = Stage::GenerateQuiets,
+                },
+                Stage::GenerateQuiets => {
+                    let moves = board.quiet_moves();
+                    self.generated += moves.len();
+                    self.quiets = moves
+                        .into_iter()
+                        .filter(|m| !self.played.contains(m))
+                        .map(|m| (m, ordering.history(&m)))
+                        .collect();
+                    self.stage = Stage::Quiets;
+                }
+                Stage::Quiets => match pop_best(&mut self.quiets) {
+                    Some(m) => return Some(m),
+                    None => {
+                        self.bad_captures.reverse();
+                        self.stage = Stage::BadCaptures;
+                    }
+                },
+                Stage::BadCaptures => match self.bad_captures.pop() {
+                    Some(m) => return Some(m),
+                    None => self.stage = Stage::Done,
+                },
+                Stage::Done => return None,
+            }
+        }
+    }
+}
+
+/// Most valuable victim, then least valuable attacker, with promotions
+/// counting the piece gained.
+pub fn mvv_lva(board: &Board, m: &Move) -> i32 {
+    let victim = match board.piece_at(m.end).kind() {
+        Some(kind) => kind.value(),
+        None if board.is_en_passant(m) => 1,
+        None => 0,
+    };
+    let attacker = m.piece.kind().map_or(0, |kind| kind.value());
+    let promotion = m
+        .promotion
+        .and_then(Piece::kind)
+        .map_or(0, |kind| kind.value());
+
+    ((victim + promotion) * 10) as i32 - attacker as i32
+}
+
+fn pop_best(moves: &mut Vec<(Move, i32)>) -> Option<Move> {
+    let best = (0..moves.len()).max_by_key(|&i| (moves[i].1, -(i as i32)))?;
+    Some(moves.remove(best).0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn san(board: &Board, s: &str) -> Move {
+        board.parse_san_move(s).unwrap()
+    }
+
+    fn board() -> Board {
+        board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . p . .
+            . . . r . . p .
+            . . . . . . . .
+            . . . . . N . .
+            . . . . . . . .
+            . . . Q K . . .
+            "
+        )
+    }
+
+    fn order(board: &Board, hash_move: Option<Move>, ordering: &MoveOrdering) -> Vec<String> {
+        let mut picker = MovePicker::new(hash_move, ordering, 2, None);
+
+        let mut moves = Vec::new();
+        while let Some(m) = picker.next(board, ordering) {
+            moves.push(board.move_to_san(&m));
+        }
+        moves
+    }
+
+    #[test]
+    fn captures_first() {
+        let board = board();
+        let ordering = MoveOrdering::default();
+        let moves = order(&board, None, &ordering);
+
+        assert_eq!(moves.len(), board.legal_moves().len());
+        assert_eq!(moves[0], "Qxd5");
+        assert_eq!(moves.last().unwrap(), "Nxg5");
+    }
+
+    #[test]
+    fn hash_move_killers_and_history() {
+        let board = board();
+        let mut ordering = MoveOrdering::default();
+
+        ordering.reward(san(&board, "Ke2"), None, 2, 1);
+        ordering.reward(san(&board, "Qa4+"), None, 2, 1);
+        ordering.reward(san(&board, "Qd2"), None, 5, 8);
+
+        let moves = order(&board, Some(san(&board, "Nh4")), &ordering);
+        assert_eq!(&moves[..5], &["Nh4", "Qxd5", "Qa4+", "Ke2", "Qd2"]);
+        assert_eq!(moves.iter().filter(|m| *m == "Nh4").count(), 1);
+        assert_eq!(moves.last().unwrap(), "Nxg5");
+
+        ordering.age();
+        assert_eq!(ordering.killers(2), [None, None]);
+        assert_eq!(ordering.history(&san(&board, "Qd2")), 32);
+    }
+
+    #[test]
+    fn countermoves() {
+        let board = board();
+        let mut ordering = MoveOrdering::default();
+        let previous = Move::new("d6".into(), "d5".into(), Piece::BlackRook);
+
+        ordering.reward(san(&board, "Qd3"), Some(previous), 9, 1);
+        assert_eq!(
+            ordering.countermove(Some(previous)),
+            Some(san(&board, "Qd3"))
+        );
+        assert_eq!(ordering.countermove(None), None);
+
+        let mut picker = MovePicker::new(None, &ordering, 2, Some(previous));
+        let moves: Vec<Move> = (0..2)
+            .filter_map(|_| picker.next(&board, &ordering))
+            .collect();
+        assert_eq!(moves[1], san(&board, "Qd3"));
+    }
+
+    #[test]
+    fn lazy_generation() {
+        let board = board();
+        let ordering = MoveOrdering::default();
+
+        // A cutoff on the hash move costs no generation at all.
+        let mut picker = MovePicker::new(Some(san(&board, "Nh4")), &ordering, 2, None);
+        assert_eq!(picker.next(&board, &ordering), Some(san(&board, "Nh4")));
+        assert_eq!(picker.generated(), 0);
+
+        // Then the captures, and the quiet moves only once those are done.
+        let captures = board.captures_and_promotions().len();
+        assert_eq!(picker.next(&board, &ordering), Some(san(&board, "Qxd5")));
+        assert_eq!(picker.generated(), captures);
+        picker.next(&board, &ordering);
+        assert_eq!(picker.generated(), board.legal_moves().len());
+
+        // Hash moves and killers from other positions are checked first.
+        let mut ordering = MoveOrdering::default();
+        let stale = Move::new("a1".into(), "a8".into(), Piece::WhiteRook);
+        ordering.reward(stale, None, 2, 1);
+        let mut picker = MovePicker::new(Some(stale), &ordering, 2, None);
+        let mut moves = Vec::new();
+        while let Some(m) = picker.next(&board, &ordering) {
+            moves.push(m);
+        }
+        assert_eq!(moves.len(), board.legal_moves().len());
+        assert!(!moves.contains(&stale));
+    }
+}
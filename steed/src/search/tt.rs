@@ -0,0 +1,228 @@
+use std::mem::size_of;
+
+use movement::Move;
+use search::MATE_BOUND;
+
+/// How a stored score relates to the true value of the position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    /// The search failed high, so the score is at least this.
+    Lower,
+    /// The search failed low, so the score is at most this.
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Entry {
+    pub key: u64,
+    pub best_move: Option<Move>,
+    score: i32,
+    pub depth: u8,
+    pub bound: Bound,
+    generation: u8,
+}
+
+impl Entry {
+    /// The score as seen from a node `ply` plies below the root. Mate
+    /// scores are stored relative to the node they were found at, so they
+    /// stay right when the position turns up at a different depth.
+    pub fn score(&self, ply: i32) -> i32 {
+        if self.score > MATE_BOUND {
+            self.score - ply
+        } else if self.score < -MATE_BOUND {
+            self.score + ply
+        } else {
+            self.score
+        }
+    }
+}
+
+/// Search results keyed by position hash, one entry per slot. A new entry
+/// replaces an old one unless the old one was searched deeper during the
+/// current search.
+#[derive(Debug, Clone)]
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+    generation: u8,
+}
+
+impl TranspositionTable {
+    /// A table taking up about `megabytes` of memory, though always with
+    /// room for at least one entry.
+    pub fn new(megabytes: usize) -> TranspositionTable {
+        let slots = (megabytes * 1024 * 1024 / size_of::<Option<Entry>>()).max(1);
+
+        TranspositionTable {
+            entries: vec![None; slots],
+            generation: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn clear(&mut self) {
+        for entry in &mut self.entries {
+            *entry = None;
+        }
+        self.generation = 0;
+    }
+
+    /// Marks everything stored so far as belonging to an earlier search, so
+    /// it gives way to new entries.
+    pub fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        self.entries[self.index(key)].filter(|entry| entry.key == key)
+    }
+
+    pub fn store(
+        &mut self,
+        key: u64,
+        depth: u32,
+        bound: Bound,
+        score: i32,
+        ply: i32,
+        best_move: Option<Move>,
+    ) {
+        let index = self.index(key);
+        let depth = depth.min(u8::MAX as u32) as u8;
+
+        if let Some(old) = self.entries[index] {
+            let replace = old.key == key || old.generation != self.generation || depth >= old.depth;
+            if !replace {
+                return;
+            }
+        }
+
+        let score = if score > MATE_BOUND {
+            score + ply
+        } else if score < -MATE_BOUND {
+            score - ply
+        } else {
+            score
+        };
+
+        // Keep the old move when the new search didn't find one, since it
+        // is still the best guess for ordering.
+        let best_move = best_move.or_else(|| {
+            self.entries[index]
+                .filter(|old| old.key == key)
+                .and_then(|old| old.best_move)
+        });
+
+        self.entries[index] = Some(Entry {
+            key,
+            best_move,
+            score,
+            depth,
+            bound,
+            generation: self.generation,
+        });
+    }
+
+    /// How full the table is in parts per thousand, counting only entries
+    /// from the current search, as reported by UCI engines.
+    pub fn hashfull(&self) -> u32 {
+        let sample = self.entries.len().min(1000);
+        let used = self.entries[..sample]
+            .iter()
+            .filter(|entry| entry.is_some_and(|entry| entry.generation == self.generation))
+            .count();
+
+        (used * 1000 / sample) as u32
+    }
+
+    fn index(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> TranspositionTable {
+        TranspositionTable::new(16)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::Piece;
+    use search::MATE;
+
+    fn knight_move() -> Move {
+        Move::new("g1".into(), "f3".into(), Piece::WhiteKnight)
+    }
+
+    #[test]
+    fn store_and_probe() {
+        let mut table = TranspositionTable::new(1);
+        assert!(table.capacity() > 1000);
+        assert_eq!(table.probe(42), None);
+
+        table.store(42, 3, Bound::Lower, 120, 2, Some(knight_move()));
+        let entry = table.probe(42).unwrap();
+        assert_eq!(entry.depth, 3);
+        assert_eq!(entry.bound, Bound::Lower);
+        assert_eq!(entry.score(5), 120);
+        assert_eq!(entry.best_move, Some(knight_move()));
+
+        let other = 42 + table.capacity() as u64;
+        assert_eq!(table.probe(other), None);
+    }
+
+    #[test]
+    fn mate_scores_are_relative() {
+        let mut table = TranspositionTable::new(1);
+
+        // Mate in 3 plies from a node 4 plies into the search.
+        table.store(7, 5, Bound::Exact, MATE - 7, 4, None);
+        assert_eq!(table.probe(7).unwrap().score(4), MATE - 7);
+        assert_eq!(table.probe(7).unwrap().score(2), MATE - 5);
+
+        table.store(8, 5, Bound::Exact, -MATE + 6, 4, None);
+        assert_eq!(table.probe(8).unwrap().score(0), -MATE + 2);
+    }
+
+    #[test]
+    fn replacement() {
+        let mut table = TranspositionTable::new(1);
+        let clash = 1 + table.capacity() as u64;
+
+        table.store(1, 6, Bound::Exact, 10, 0, Some(knight_move()));
+        table.store(clash, 2, Bound::Exact, 20, 0, None);
+        assert_eq!(table.probe(1).unwrap().score(0), 10);
+        assert_eq!(table.probe(clash), None);
+
+        table.store(1, 1, Bound::Upper, 30, 0, None);
+        let entry = table.probe(1).unwrap();
+        assert_eq!(entry.score(0), 30);
+        assert_eq!(entry.best_move, Some(knight_move()));
+
+        table.store(1, 6, Bound::Exact, 10, 0, None);
+        table.new_search();
+        table.store(clash, 2, Bound::Exact, 20, 0, None);
+        assert_eq!(table.probe(clash).unwrap().score(0), 20);
+    }
+
+    #[test]
+    fn hashfull() {
+        let mut table = TranspositionTable::new(1);
+        assert_eq!(table.hashfull(), 0);
+
+        for key in 0..100 {
+            table.store(key, 1, Bound::Exact, 0, 0, None);
+        }
+        assert_eq!(table.hashfull(), 100);
+
+        table.new_search();
+        assert_eq!(table.hashfull(), 0);
+
+        table.clear();
+        assert_eq!(table.probe(5), None);
+    }
+}
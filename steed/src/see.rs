@@ -0,0 +1,180 @@
+use board::{Board, Color, Piece, PieceKind};
+use movement::Move;
+use position::Position;
+
+impl Board {
+    /// Static exchange evaluation: the material `m` wins or loses in
+    /// centipawns once both sides have made every worthwhile recapture on
+    /// its destination, always using their least valuable attacker. Pieces
+    /// lined up behind one another join in as the front one is traded off,
+    /// but pins are not taken into account.
+    pub fn see(&self, m: &Move) -> i32 {
+        let target = m.end;
+        let mut board = self.clone();
+        let mut gains = vec![self.captured_value(m)];
+
+        if self.is_en_passant(m) {
+            let behind = Position::new(target.column, m.start.row);
+            board.add_piece(Piece::Empty, behind);
+        }
+
+        let moved = m.promotion.unwrap_or(m.piece);
+        if let Some(promotion) = m.promotion {
+            gains[0] += value(promotion) - value(m.piece);
+        }
+        board.add_piece(Piece::Empty, m.start);
+        board.add_piece(moved, target);
+
+        let mut on_target = value(moved);
+        let mut side = m.piece.color().opposite();
+
+        while let Some(attacker) = least_valuable_attacker(&board, target, side) {
+            let gain = on_target - gains[gains.len() - 1];
+            // Neither side can do better by carrying on from here.
+            if (-gains[gains.len() - 1]).max(gain) < 0 {
+                break;
+            }
+            gains.push(gain);
+
+            let piece = board.piece_at(attacker);
+            board.add_piece(Piece::Empty, attacker);
+            board.add_piece(piece, target);
+            on_target = value(piece);
+            side = side.opposite();
+        }
+
+        while gains.len() > 1 {
+            let last = gains.pop().unwrap();
+            let previous = gains.len() - 1;
+            gains[previous] = -(-gains[previous]).max(last);
+        }
+
+        gains[0]
+    }
+
+    /// Whether `see(m)` is at least `threshold`.
+    pub fn see_ge(&self, m: &Move, threshold: i32) -> bool {
+        self.see(m) >= threshold
+    }
+
+    fn captured_value(&self, m: &Move) -> i32 {
+        if self.is_en_passant(m) {
+            return value(Piece::WhitePawn);
+        }
+
+        match self.piece_at(m.end) {
+            Piece::Empty => 0,
+            piece => value(piece),
+        }
+    }
+}
+
+// The king is worth more than everything else together, so trading it
+// into an attacked square never pays.
+fn value(piece: Piece) -> i32 {
+    match piece.kind() {
+        Some(PieceKind::King) => 20_000,
+        Some(kind) => kind.value() as i32 * 100,
+        None => 0,
+    }
+}
+
+fn least_valuable_attacker(board: &Board, target: Position, color: Color) -> Option<Position> {
+    board
+        .attackers_of(target, color)
+        .into_iter()
+        .min_by_key(|&position| value(board.piece_at(position)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn see(board: &Board, san: &str) -> i32 {
+        let m = board.parse_san_move(san).unwrap();
+        board.see(&m)
+    }
+
+    #[test]
+    fn undefended_and_defended() {
+        let mut board = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . p . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . R . K .
+            "
+        );
+        assert_eq!(see(&board, "Rxe5"), 100);
+
+        board.add_piece(Piece::BlackPawn, "d6");
+        assert_eq!(see(&board, "Rxe5"), -400);
+        assert!(board.see_ge(&board.parse_san_move("Rxe5").unwrap(), -400));
+        assert!(!board.see_ge(&board.parse_san_move("Rxe5").unwrap(), 0));
+    }
+
+    #[test]
+    fn x_rays() {
+        let mut board = board!(
+            "
+            . . . . r . k .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . p . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . R . . .
+            . . . . . . K .
+            "
+        );
+        assert_eq!(see(&board, "Rxe5"), -400);
+
+        board.add_piece(Piece::WhiteRook, "e1");
+        assert_eq!(see(&board, "R2xe5"), 100);
+    }
+
+    #[test]
+    fn bishop_takes_defended_pawn() {
+        let board = board!(
+            "
+            . . . . k . . .
+            . . . . . . . .
+            . . n . . . . .
+            . . . . p . . .
+            . . . . . . . .
+            . . B . . N . .
+            . . . . . . . .
+            . . . . K . . .
+            "
+        );
+        assert_eq!(see(&board, "Bxe5"), 100);
+        assert_eq!(see(&board, "Nxe5"), 100);
+    }
+
+    #[test]
+    fn quiet_moves_and_promotions() {
+        let board = board!(
+            "
+            . n . . k . . .
+            P . . . . . . .
+            . . . . . . . .
+            . . . p . p . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . . . . . .
+            . . . Q K . . .
+            "
+        );
+        assert_eq!(see(&board, "Qd2"), 0);
+        assert_eq!(see(&board, "Qd4"), 0);
+        assert_eq!(see(&board, "Qc2"), 0);
+        assert_eq!(see(&board, "Qa4"), 0);
+        assert_eq!(see(&board, "Qg4"), -900);
+        assert_eq!(see(&board, "Qxd5"), 100);
+        assert_eq!(see(&board, "axb8=Q"), 300 + 800);
+    }
+}
@@ -0,0 +1,367 @@
+use std::fmt;
+use std::iter::FromIterator;
+use std::str::FromStr;
+
+use board::Color;
+use position::{Column, Position};
+
+/// One of the 64 squares, numbered from a1 = 0 along each rank up to
+/// h8 = 63. Converts to and from `Position`, so it can be passed anywhere
+/// the board takes a position.
+#[derive(Debug, Clone, Copy, PartialOrd, Ord, PartialEq, Eq, Hash)]
+pub struct Square(u8);
+
+macro_rules! squares {
+    ($($name:ident = $index:expr,)*) => {
+        impl Square {
+            $(pub const $name: Square = Square($index);)*
+        }
+    };
+}
+
+squares! {
+    A1 = 0, B1 = 1, C1 = 2, D1 = 3, E1 = 4, F1 = 5, G1 = 6, H1 = 7,
+    A2 = 8, B2 = 9, C2 = 10, D2 = 11, E2 = 12, F2 = 13, G2 = 14, H2 = 15,
+    A3 = 16, B3 = 17, C3 = 18, D3 = 19, E3 = 20, F3 = 21, G3 = 22, H3 = 23,
+    A4 = 24, B4 = 25, C4 = 26, D4 = 27, E4 = 28, F4 = 29, G4 = 30, H4 = 31,
+    A5 = 32, B5 = 33, C5 = 34, D5 = 35, E5 = 36, F5 = 37, G5 = 38, H5 = 39,
+    A6 = 40, B6 = 41, C6 = 42, D6 = 43, E6 = 44, F6 = 45, G6 = 46, H6 = 47,
+    A7 = 48, B7 = 49, C7 = 50, D7 = 51, E7 = 52, F7 = 53, G7 = 54, H7 = 55,
+    A8 = 56, B8 = 57, C8 = 58, D8 = 59, E8 = 60, F8 = 61, G8 = 62, H8 = 63,
+}
+
+impl Square {
+    /// `file` and `rank` count from 0, so `Square::new(4, 3)` is e4.
+    pub fn new(file: u8, rank: u8) -> Square {
+        assert!(file < 8 && rank < 8, "square off the board");
+        Square(rank * 8 + file)
+    }
+
+    pub fn from_index(index: usize) -> Option<Square> {
+        if index < 64 {
+            Some(Square(index as u8))
+        } else {
+            None
+        }
+    }
+
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn file(self) -> Column {
+        Column::from((self.0 % 8 + 1) as i8)
+    }
+
+    /// The rank from 1 to 8, matching `Position::row`.
+    pub fn rank(self) -> u8 {
+        self.0 / 8 + 1
+    }
+
+    /// Every square from a1 to h8.
+    pub fn all() -> impl Iterator<Item = Square> {
+        (0..64).map(Square)
+    }
+
+    /// The square `files` and `ranks` away, if it is still on the board.
+    pub fn offset(self, files: i8, ranks: i8) -> Option<Square> {
+        let file = (self.0 % 8) as i8 + files;
+        let rank = (self.0 / 8) as i8 + ranks;
+
+        if (0..8).contains(&file) && (0..8).contains(&rank) {
+            Some(Square::new(file as u8, rank as u8))
+        } else {
+            None
+        }
+    }
+
+    /// King moves needed to get from one square to the other.
+    pub fn distance(self, other: Square) -> u8 {
+        let (files, ranks) = self.delta(other);
+        files.abs().max(ranks.abs()) as u8
+    }
+
+    /// Rook moves on an empty board if the rook could only move one square
+    /// at a time.
+    pub fn manhattan_distance(self, other: Square) -> u8 {
+        let (files, ranks) = self.delta(other);
+        (files.abs() + ranks.abs()) as u8
+    }
+
+    /// a1 is a dark square, so its color is black.
+    pub fn color(self) -> Color {
+        if (self.0 % 8 + self.0 / 8) & 1 == 0 {
+            Color::Black
+        } else {
+            Color::White
+        }
+    }
+
+    /// The square on the same file seen from the other side, e.g. e2 and e7.
+    pub fn flip_vertical(self) -> Square {
+        Square(self.0 ^ 56)
+    }
+
+    /// The square on the same rank mirrored across the board, e.g. b1 and g1.
+    pub fn flip_horizontal(self) -> Square {
+        Square(self.0 ^ 7)
+    }
+
+    /// Every square stepping `files` and `ranks` at a time until the edge of
+    /// the board, not including this one.
+    pub fn ray(self, files: i8, ranks: i8) -> SquareSet {
+        let mut ray = SquareSet::new();
+        let mut current = self;
+
+        while let Some(next) = current.offset(files, ranks) {
+            ray.insert(next);
+            current = next;
+        }
+
+        ray
+    }
+
+    /// The squares strictly between two squares on the same rank, file or
+    /// diagonal; empty when they aren't lined up.
+    pub fn between(self, other: Square) -> SquareSet {
+        match self.direction(other) {
+            Some((files, ranks)) => self
+                .ray(files, ranks)
+                .intersection(other.ray(-files, -ranks)),
+            None => SquareSet::new(),
+        }
+    }
+
+    /// The whole rank, file or diagonal through both squares, edge to edge;
+    /// empty when they aren't lined up.
+    pub fn line(self, other: Square) -> SquareSet {
+        match self.direction(other) {
+            Some((files, ranks)) => {
+                let mut line = self.ray(files, ranks).union(self.ray(-files, -ranks));
+                line.insert(self);
+                line
+            }
+            None => SquareSet::new(),
+        }
+    }
+
+    fn delta(self, other: Square) -> (i8, i8) {
+        let files = (other.0 % 8) as i8 - (self.0 % 8) as i8;
+        let ranks = (other.0 / 8) as i8 - (self.0 / 8) as i8;
+        (files, ranks)
+    }
+
+    // The single step from this square towards a different one on the same
+    // line, if there is one.
+    fn direction(self, other: Square) -> Option<(i8, i8)> {
+        let (files, ranks) = self.delta(other);
+        let aligned = files == 0 || ranks == 0 || files.abs() == ranks.abs();
+
+        if self != other && aligned {
+            Some((files.signum(), ranks.signum()))
+        } else {
+            None
+        }
+    }
+}
+
+impl From<Position> for Square {
+    fn from(position: Position) -> Square {
+        let file: i8 = position.column.into();
+        Square::new(file as u8 - 1, position.row - 1)
+    }
+}
+
+impl From<Square> for Position {
+    fn from(square: Square) -> Position {
+        Position::new(square.file(), square.rank())
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", Position::from(*self))
+    }
+}
+
+impl FromStr for Square {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Square, &'static str> {
+        s.parse::<Position>().map(Square::from)
+    }
+}
+
+/// A set of squares stored as one bit per square.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SquareSet(u64);
+
+impl SquareSet {
+    pub fn new() -> SquareSet {
+        SquareSet(0)
+    }
+
+    pub fn contains(self, square: Square) -> bool {
+        self.0 & (1 << square.0) != 0
+    }
+
+    pub fn insert(&mut self, square: Square) {
+        self.0 |= 1 << square.0;
+    }
+
+    pub fn remove(&mut self, square: Square) {
+        self.0 &= !(1 << square.0);
+    }
+
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn union(self, other: SquareSet) -> SquareSet {
+        SquareSet(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: SquareSet) -> SquareSet {
+        SquareSet(self.0 & other.0)
+    }
+
+    /// The squares in order from a1 to h8.
+    pub fn iter(self) -> SquareSetIter {
+        SquareSetIter(self.0)
+    }
+}
+
+impl FromIterator<Square> for SquareSet {
+    fn from_iter<I: IntoIterator<Item = Square>>(iter: I) -> SquareSet {
+        let mut set = SquareSet::new();
+        for square in iter {
+            set.insert(square);
+        }
+
+        set
+    }
+}
+
+impl IntoIterator for SquareSet {
+    type Item = Square;
+    type IntoIter = SquareSetIter;
+
+    fn into_iter(self) -> SquareSetIter {
+        self.iter()
+    }
+}
+
+pub struct SquareSetIter(u64);
+
+impl Iterator for SquareSetIter {
+    type Item = Square;
+
+    fn next(&mut self) -> Option<Square> {
+        if self.0 == 0 {
+            return None;
+        }
+
+        let index = self.0.trailing_zeros() as u8;
+        self.0 &= self.0 - 1;
+        Some(Square(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(squares: &[Square]) -> SquareSet {
+        squares.iter().cloned().collect()
+    }
+
+    #[test]
+    fn constants() {
+        assert_eq!(Square::A1.index(), 0);
+        assert_eq!(Square::H8.index(), 63);
+        assert_eq!(Square::E4, Square::new(4, 3));
+        assert_eq!(Square::E4.file(), Column::E);
+        assert_eq!(Square::E4.rank(), 4);
+        assert_eq!(Square::E4.to_string(), "e4");
+        assert_eq!("g7".parse(), Ok(Square::G7));
+        assert_eq!(Square::from_index(64), None);
+    }
+
+    #[test]
+    fn positions() {
+        assert_eq!(Position::from(Square::C6), "c6".into());
+        assert_eq!(Square::from(Position::from("h1")), Square::H1);
+
+        let indices: Vec<usize> = Square::all().map(|s| Position::from(s).into()).collect();
+        assert_eq!(indices.len(), 64);
+        assert_eq!(indices[0], 56);
+        assert_eq!(indices[63], 7);
+    }
+
+    #[test]
+    fn distances() {
+        assert_eq!(Square::A1.distance(Square::H8), 7);
+        assert_eq!(Square::A1.manhattan_distance(Square::H8), 14);
+        assert_eq!(Square::E4.distance(Square::F6), 2);
+        assert_eq!(Square::E4.manhattan_distance(Square::F6), 3);
+        assert_eq!(Square::E4.distance(Square::E4), 0);
+    }
+
+    #[test]
+    fn colors_and_mirrors() {
+        assert_eq!(Square::A1.color(), Color::Black);
+        assert_eq!(Square::H1.color(), Color::White);
+        assert_eq!(Square::D1.color(), Color::White);
+        assert_eq!(Square::E1.color(), Color::Black);
+
+        assert_eq!(Square::E2.flip_vertical(), Square::E7);
+        assert_eq!(Square::B1.flip_horizontal(), Square::G1);
+        assert_eq!(Square::A1.offset(-1, 0), None);
+        assert_eq!(Square::A1.offset(2, 1), Some(Square::C2));
+    }
+
+    #[test]
+    fn rays_and_lines() {
+        assert_eq!(Square::F6.ray(1, 1), set(&[Square::G7, Square::H8]));
+        assert!(Square::H8.ray(1, 1).is_empty());
+        assert_eq!(Square::A1.ray(0, 1).len(), 7);
+
+        assert_eq!(
+            Square::C1.between(Square::F4),
+            set(&[Square::D2, Square::E3])
+        );
+        assert_eq!(
+            Square::E1.between(Square::E4),
+            set(&[Square::E2, Square::E3])
+        );
+        assert!(Square::E1.between(Square::E2).is_empty());
+        assert!(Square::E1.between(Square::F3).is_empty());
+
+        assert_eq!(Square::C1.line(Square::D2).len(), 6);
+        assert!(!Square::C1.line(Square::D2).contains(Square::A3));
+        assert!(Square::C1.line(Square::D2).contains(Square::H6));
+        assert_eq!(Square::B2.line(Square::B5).len(), 8);
+        assert!(Square::B2.line(Square::C4).is_empty());
+    }
+
+    #[test]
+    fn square_sets() {
+        let mut squares = set(&[Square::H8, Square::A1, Square::D4]);
+        assert_eq!(squares.len(), 3);
+        assert_eq!(
+            squares.iter().collect::<Vec<Square>>(),
+            vec![Square::A1, Square::D4, Square::H8]
+        );
+
+        squares.remove(Square::D4);
+        assert!(!squares.contains(Square::D4));
+        assert_eq!(
+            squares.intersection(set(&[Square::A1, Square::B1])),
+            set(&[Square::A1])
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use board::{Board, CastlingRights, Piece};
+use position::Position;
+use square::Square;
+
+impl Board {
+    /// The same position with the colors swapped: white's pieces become
+    /// black's on the mirrored rank, and the side to move, castling rights
+    /// and en passant square follow them. The new board has no history.
+    pub fn flip_colors(&self) -> Board {
+        let mut board = self.transform(Square::flip_vertical, true);
+
+        let rights = self.castling();
+        board.set_castling(CastlingRights {
+            white_kingside: rights.black_kingside,
+            white_queenside: rights.black_queenside,
+            black_kingside: rights.white_kingside,
+            black_queenside: rights.white_queenside,
+        });
+        board.to_move = self.to_move.opposite();
+
+        board
+    }
+
+    /// The position reflected from the a-file to the h-file. Castling only
+    /// works one way round, so there is no mirror image while any castling
+    /// rights remain.
+    pub fn mirror_horizontal(&self) -> Option<Board> {
+        if self.castling() != CastlingRights::none() {
+            return None;
+        }
+
+        let mut board = self.transform(Square::flip_horizontal, false);
+        board.set_castling(CastlingRights::none());
+        board.to_move = self.to_move;
+
+        Some(board)
+    }
+
+    /// The board turned through 180 degrees with the colors swapped, so
+    /// that it shows the same game from the other player's chair. Needs
+    /// the same lack of castling rights as `mirror_horizontal`.
+    pub fn rotate(&self) -> Option<Board> {
+        self.mirror_horizontal().map(|board| board.flip_colors())
+    }
+
+    fn transform(&self, map: fn(Square) -> Square, swap_colors: bool) -> Board {
+        let mut board = Board::empty();
+
+        for (i, &piece) in self.enumerate_pieces() {
+            if let Some(kind) = piece.kind() {
+                let color = if swap_colors {
+                    piece.color().opposite()
+                } else {
+                    piece.color()
+                };
+                let square = map(Square::from(Position::from(i)));
+                board.add_piece(Piece::new(kind, color), square);
+            }
+        }
+
+        let en_passant = self
+            .en_passant()
+            .map(|position| Position::from(map(Square::from(position))));
+        board.set_en_passant(en_passant);
+
+        board
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::Color;
+
+    fn kiwipete() -> Board {
+        let mut board = board!(
+            "
+            r . . . k . . r
+            p . p p q p b .
+            b n . . p n p .
+            . . . P N . . .
+            . p . . P . . .
+            . . N . . Q . p
+            P P P B B P P P
+            R . . . K . . R
+            "
+        );
+        board.to_move = Color::White;
+        board
+    }
+
+    #[test]
+    fn flip_colors() {
+        let board = kiwipete();
+        let flipped = board.flip_colors();
+
+        assert_eq!(flipped.piece_at("e8"), Piece::BlackKing);
+        assert_eq!(flipped.piece_at("f6"), Piece::BlackQueen);
+        assert_eq!(flipped.piece_at("e2"), Piece::WhiteQueen);
+        assert_eq!(flipped.piece_at("d4"), Piece::BlackPawn);
+        assert_eq!(flipped.to_move, Color::Black);
+        assert_eq!(flipped.castling(), CastlingRights::all());
+        assert_eq!(flipped.legal_moves().len(), board.legal_moves().len());
+        assert_eq!(flipped.flip_colors().to_string(), board.to_string());
+    }
+
+    #[test]
+    fn flip_en_passant() {
+        let mut board = Board::default();
+        board.move_piece("e2", "e4");
+        board.move_piece("a7", "a6");
+        board.move_piece("e4", "e5");
+        board.move_piece("d7", "d5");
+
+        let flipped = board.flip_colors();
+        assert_eq!(flipped.en_passant(), Some("d3".into()));
+        assert_eq!(flipped.to_move, Color::Black);
+        assert_eq!(flipped.legal_moves().len(), board.legal_moves().len());
+    }
+
+    #[test]
+    fn mirror_and_rotate() {
+        assert!(kiwipete().mirror_horizontal().is_none());
+        assert!(kiwipete().rotate().is_none());
+
+        let mut board = kiwipete();
+        board.set_castling(CastlingRights::none());
+        let count = board.legal_moves().len();
+
+        let mirrored = board.mirror_horizontal().unwrap();
+        assert_eq!(mirrored.piece_at("d1"), Piece::WhiteKing);
+        assert_eq!(mirrored.piece_at("c3"), Piece::WhiteQueen);
+        assert_eq!(mirrored.legal_moves().len(), count);
+
+        let rotated = board.rotate().unwrap();
+        assert_eq!(rotated.piece_at("d8"), Piece::BlackKing);
+        assert_eq!(rotated.to_move, Color::Black);
+        assert_eq!(rotated.legal_moves().len(), count);
+        assert_eq!(rotated.rotate().unwrap().to_string(), board.to_string());
+    }
+}
@@ -0,0 +1,325 @@
+use std::time::Duration;
+
+use board::Board;
+use movement::Move;
+
+pub type NodeId = usize;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Node {
+    /// The move leading to this node; only the root has none.
+    pub m: Option<Move>,
+    pub parent: Option<NodeId>,
+    /// Replies to this move. The first is the main line, the rest are
+    /// variations.
+    pub children: Vec<NodeId>,
+    /// Comments ahead of the move, as at the start of a variation.
+    pub comments_before: Vec<String>,
+    pub comments: Vec<String>,
+    /// Numeric annotation glyphs, e.g. 1 for `!` or 14 for `$14`.
+    pub nags: Vec<u8>,
+    /// Embedded commands such as `[%clk 0:01:00]`, as name and value.
+    pub annotations: Vec<(String, String)>,
+}
+
+impl Node {
+    fn new(m: Option<Move>, parent: Option<NodeId>) -> Node {
+        Node {
+            m,
+            parent,
+            children: Vec::new(),
+            comments_before: Vec::new(),
+            comments: Vec::new(),
+            nags: Vec::new(),
+            annotations: Vec::new(),
+        }
+    }
+
+    pub fn annotation(&self, name: &str) -> Option<&str> {
+        self.annotations
+            .iter()
+            .find(|a| a.0 == name)
+            .map(|a| a.1.as_str())
+    }
+
+    pub fn set_annotation(&mut self, name: &str, value: &str) {
+        match self.annotations.iter_mut().find(|a| a.0 == name) {
+            Some(a) => a.1 = value.to_string(),
+            None => self.annotations.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Remaining clock time from a `clk` annotation.
+    pub fn clock(&self) -> Option<Duration> {
+        let mut seconds = 0.0;
+        for part in self.annotation("clk")?.split(':') {
+            seconds = seconds * 60.0 + part.parse::<f64>().ok()?;
+        }
+
+        Some(Duration::from_millis((seconds * 1000.0).round() as u64))
+    }
+
+    pub fn set_clock(&mut self, clock: Duration) {
+        let seconds = clock.as_secs();
+        let value = format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        );
+        self.set_annotation("clk", &value);
+    }
+}
+
+/// A game with all of its variations. Nodes live in an arena and are
+/// addressed by `NodeId`; the ids of deleted nodes are never reused.
+#[derive(Debug, Clone)]
+pub struct GameTree {
+    start: Board,
+    nodes: Vec<Option<Node>>,
+}
+
+impl GameTree {
+    pub fn new(start: Board) -> GameTree {
+        GameTree {
+            start,
+            nodes: vec![Some(Node::new(None, None))],
+        }
+    }
+
+    pub fn root(&self) -> NodeId {
+        0
+    }
+
+    pub fn start(&self) -> &Board {
+        &self.start
+    }
+
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.nodes.get(id).is_some_and(|node| node.is_some())
+    }
+
+    /// Panics if `id` was deleted.
+    pub fn node(&self, id: NodeId) -> &Node {
+        self.nodes[id].as_ref().expect("node was deleted")
+    }
+
+    /// Panics if `id` was deleted.
+    pub fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        self.nodes[id].as_mut().expect("node was deleted")
+    }
+
+    /// Adds `m` as a reply to `parent`, becoming the main line if it is the
+    /// first. If the move is already there its node is returned instead.
+    pub fn add_move(&mut self, parent: NodeId, m: Move) -> NodeId {
+        if let Some(&existing) = self
+            .node(parent)
+            .children
+            .iter()
+            .find(|&&child| self.node(child).m == Some(m))
+        {
+            return existing;
+        }
+
+        let id = self.nodes.len();
+        self.nodes.push(Some(Node::new(Some(m), Some(parent))));
+        self.node_mut(parent).children.push(id);
+
+        id
+    }
+
+    pub fn mainline(&self) -> Vec<NodeId> {
+        let mut line = Vec::new();
+        let mut id = self.root();
+
+        while let Some(&child) = self.node(id).children.first() {
+            line.push(child);
+            id = child;
+        }
+
+        line
+    }
+
+    pub fn mainline_moves(&self) -> Vec<Move> {
+        self.mainline()
+            .iter()
+            .filter_map(|&id| self.node(id).m)
+            .collect()
+    }
+
+    pub fn is_mainline(&self, id: NodeId) -> bool {
+        id == self.root() || self.mainline().contains(&id)
+    }
+
+    /// The nodes from just after the root down to `id`.
+    pub fn path(&self, id: NodeId) -> Vec<NodeId> {
+        let mut path = Vec::new();
+        let mut current = id;
+
+        while let Some(parent) = self.node(current).parent {
+            path.push(current);
+            current = parent;
+        }
+
+        path.reverse();
+        path
+    }
+
+    pub fn moves_to(&self, id: NodeId) -> Vec<Move> {
+        self.path(id)
+            .iter()
+            .filter_map(|&id| self.node(id).m)
+            .collect()
+    }
+
+    /// The position after the move at `id`.
+    pub fn board_at(&self, id: NodeId) -> Board {
+        let mut board = self.start.clone();
+        for m in self.moves_to(id) {
+            board.make_move(m);
+        }
+
+        board
+    }
+
+    /// Moves `id` one place up among its siblings, so that a variation
+    /// swaps with the one before it or replaces the main line.
+    pub fn promote_variation(&mut self, id: NodeId) {
+        if let Some(parent) = self.node(id).parent {
+            let children = &mut self.node_mut(parent).children;
+            if let Some(index) = children.iter().position(|&child| child == id) {
+                if index > 0 {
+                    children.swap(index, index - 1);
+                }
+            }
+        }
+    }
+
+    /// Makes `id` and all of its ancestors the first choice at each branch,
+    /// so that `id` ends up on the main line of the game.
+    pub fn promote_to_mainline(&mut self, id: NodeId) {
+        let mut current = id;
+
+        while let Some(parent) = self.node(current).parent {
+            let children = &mut self.node_mut(parent).children;
+            if let Some(index) = children.iter().position(|&child| child == current) {
+                let child = children.remove(index);
+                children.insert(0, child);
+            }
+            current = parent;
+        }
+    }
+
+    /// Removes `id` and everything after it. Deleting the root clears the
+    /// whole game but keeps the root itself.
+    pub fn delete_subtree(&mut self, id: NodeId) {
+        if let Some(parent) = self.node(id).parent {
+            self.node_mut(parent).children.retain(|&child| child != id);
+        }
+
+        let mut stack = self.node(id).children.clone();
+        while let Some(current) = stack.pop() {
+            stack.extend(self.node(current).children.iter());
+            self.nodes[current] = None;
+        }
+
+        if id == self.root() {
+            self.node_mut(id).children.clear();
+        } else {
+            self.nodes[id] = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add(tree: &mut GameTree, parent: NodeId, san: &str) -> NodeId {
+        let m = tree.board_at(parent).parse_san_move(san).unwrap();
+        tree.add_move(parent, m)
+    }
+
+    // 1. e4 e5 (1... c5 2. Nf3) (1... e6) 2. Nf3
+    fn sample() -> (GameTree, Vec<NodeId>) {
+        let mut tree = GameTree::new(Board::default());
+        let root = tree.root();
+        let e4 = add(&mut tree, root, "e4");
+        let e5 = add(&mut tree, e4, "e5");
+        let c5 = add(&mut tree, e4, "c5");
+        let nf3_sicilian = add(&mut tree, c5, "Nf3");
+        let e6 = add(&mut tree, e4, "e6");
+        let nf3 = add(&mut tree, e5, "Nf3");
+
+        (tree, vec![e4, e5, c5, nf3_sicilian, e6, nf3])
+    }
+
+    #[test]
+    fn mainline_and_variations() {
+        let (tree, ids) = sample();
+
+        assert_eq!(tree.mainline(), vec![ids[0], ids[1], ids[5]]);
+        assert_eq!(tree.node(ids[0]).children, vec![ids[1], ids[2], ids[4]]);
+        assert!(tree.is_mainline(ids[1]));
+        assert!(!tree.is_mainline(ids[3]));
+        assert_eq!(tree.path(ids[3]), vec![ids[0], ids[2], ids[3]]);
+
+        let board = tree.board_at(ids[3]);
+        assert_eq!(board.history().len(), 3);
+        assert_eq!(board.piece_at("c5"), ::board::Piece::BlackPawn);
+    }
+
+    #[test]
+    fn adding_an_existing_move() {
+        let (mut tree, ids) = sample();
+        assert_eq!(add(&mut tree, ids[0], "c5"), ids[2]);
+        assert_eq!(tree.node(ids[0]).children.len(), 3);
+    }
+
+    #[test]
+    fn promote() {
+        let (mut tree, ids) = sample();
+
+        tree.promote_variation(ids[4]);
+        assert_eq!(tree.node(ids[0]).children, vec![ids[1], ids[4], ids[2]]);
+
+        tree.promote_variation(ids[4]);
+        assert_eq!(tree.node(ids[0]).children, vec![ids[4], ids[1], ids[2]]);
+        assert_eq!(tree.mainline(), vec![ids[0], ids[4]]);
+
+        tree.promote_to_mainline(ids[3]);
+        assert_eq!(tree.mainline(), vec![ids[0], ids[2], ids[3]]);
+        assert_eq!(tree.node(ids[0]).children, vec![ids[2], ids[4], ids[1]]);
+    }
+
+    #[test]
+    fn delete() {
+        let (mut tree, ids) = sample();
+
+        tree.delete_subtree(ids[2]);
+        assert!(!tree.contains(ids[2]));
+        assert!(!tree.contains(ids[3]));
+        assert_eq!(tree.node(ids[0]).children, vec![ids[1], ids[4]]);
+
+        let root = tree.root();
+        tree.delete_subtree(root);
+        assert!(tree.contains(root));
+        assert!(!tree.contains(ids[0]));
+        assert!(tree.mainline().is_empty());
+    }
+
+    #[test]
+    fn annotations() {
+        let (mut tree, ids) = sample();
+        let node = tree.node_mut(ids[0]);
+
+        node.set_clock(Duration::from_secs(3725));
+        node.set_annotation("eval", "0.25");
+        assert_eq!(node.annotation("clk"), Some("1:02:05"));
+        assert_eq!(node.clock(), Some(Duration::from_secs(3725)));
+        assert_eq!(node.annotation("eval"), Some("0.25"));
+
+        node.set_annotation("clk", "0:00:09.5");
+        assert_eq!(node.clock(), Some(Duration::from_millis(9500)));
+    }
+}
@@ -0,0 +1,180 @@
+use std::fmt;
+
+use board::{Board, Color, Piece};
+use movement::Move;
+use position::{Column, Position};
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum UciError {
+    /// The text is not of the form `e2e4` or `e7e8q`.
+    Invalid,
+    /// No legal move goes between the given squares.
+    Illegal,
+}
+
+impl fmt::Display for UciError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UciError::Invalid => write!(f, "invalid UCI move"),
+            UciError::Illegal => write!(f, "illegal UCI move"),
+        }
+    }
+}
+
+impl Move {
+    /// Long algebraic notation as used by UCI engines; castling is written
+    /// as the king's two square move, e.g. `e1g1`.
+    pub fn to_uci(&self) -> String {
+        let mut uci = format!("{}{}", self.start, self.end);
+
+        if let Some(piece) = self.promotion {
+            uci.push(promotion_letter(piece));
+        }
+
+        uci
+    }
+}
+
+fn promotion_letter(piece: Piece) -> char {
+    match piece {
+        Piece::WhiteQueen | Piece::BlackQueen => 'q',
+        Piece::WhiteRook | Piece::BlackRook => 'r',
+        Piece::WhiteBishop | Piece::BlackBishop => 'b',
+        _ => 'n',
+    }
+}
+
+impl Board {
+    /// Reads a move in long algebraic notation. Castling may be given either
+    /// as the king's move (`e1g1`) or Chess960 style as the king taking its
+    /// own rook (`e1h1`).
+    pub fn parse_uci_move(&self, s: &str) -> Result<Move, UciError> {
+        let s = s.trim();
+        if !s.is_ascii() || s.len() < 4 || s.len() > 5 {
+            return Err(UciError::Invalid);
+        }
+
+        let start: Position = s[0..2].parse().map_err(|_| UciError::Invalid)?;
+        let mut end: Position = s[2..4].parse().map_err(|_| UciError::Invalid)?;
+        let promotion = match s[4..].chars().next().map(|c| c.to_ascii_lowercase()) {
+            None => None,
+            Some(c @ 'q') | Some(c @ 'r') | Some(c @ 'b') | Some(c @ 'n') => Some(c),
+            Some(_) => return Err(UciError::Invalid),
+        };
+
+        if self.is_king_takes_rook(start, end) {
+            let column = if end.column > start.column {
+                Column::G
+            } else {
+                Column::C
+            };
+            end = Position::new(column, end.row);
+        }
+
+        self.legal_moves()
+            .into_iter()
+            .find(|m| {
+                m.start == start && m.end == end && m.promotion.map(promotion_letter) == promotion
+            })
+            .ok_or(UciError::Illegal)
+    }
+
+    fn is_king_takes_rook(&self, start: Position, end: Position) -> bool {
+        match (self.piece_at(start), self.piece_at(end)) {
+            (Piece::WhiteKing, Piece::WhiteRook) => self.to_move == Color::White,
+            (Piece::BlackKing, Piece::BlackRook) => self.to_move == Color::Black,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_moves() {
+        let m = Move::new("e2".into(), "e4".into(), Piece::WhitePawn);
+        assert_eq!(m.to_uci(), "e2e4");
+
+        let m = Move::new("e8".into(), "c8".into(), Piece::BlackKing);
+        assert_eq!(m.to_uci(), "e8c8");
+
+        let m = Move::promote(
+            "b2".into(),
+            "a1".into(),
+            Piece::BlackPawn,
+            Piece::BlackKnight,
+        );
+        assert_eq!(m.to_uci(), "b2a1n");
+    }
+
+    #[test]
+    fn parse_moves() {
+        let board = Board::default();
+        assert_eq!(
+            board.parse_uci_move("g1f3"),
+            Ok(Move::new("g1".into(), "f3".into(), Piece::WhiteKnight))
+        );
+        assert_eq!(board.parse_uci_move("e2e5"), Err(UciError::Illegal));
+        assert_eq!(board.parse_uci_move("e7e5"), Err(UciError::Illegal));
+        assert_eq!(board.parse_uci_move("e2"), Err(UciError::Invalid));
+        assert_eq!(board.parse_uci_move("e2e4x"), Err(UciError::Invalid));
+        assert_eq!(board.parse_uci_move("z2e4"), Err(UciError::Invalid));
+    }
+
+    #[test]
+    fn parse_promotions() {
+        let mut board = Board::empty();
+        board.add_piece(Piece::WhitePawn, "g7");
+
+        assert_eq!(
+            board.parse_uci_move("g7g8r"),
+            Ok(Move::promote(
+                "g7".into(),
+                "g8".into(),
+                Piece::WhitePawn,
+                Piece::WhiteRook
+            ))
+        );
+        assert_eq!(
+            board.parse_uci_move("g7g8Q"),
+            Ok(Move::promote(
+                "g7".into(),
+                "g8".into(),
+                Piece::WhitePawn,
+                Piece::WhiteQueen
+            ))
+        );
+        assert_eq!(board.parse_uci_move("g7g8"), Err(UciError::Illegal));
+    }
+
+    #[test]
+    fn parse_castling() {
+        let mut board = Board::empty();
+        board.add_piece(Piece::WhiteKing, "e1");
+        board.add_piece(Piece::WhiteRook, "a1");
+        board.add_piece(Piece::WhiteRook, "h1");
+
+        let kingside = Move::new("e1".into(), "g1".into(), Piece::WhiteKing);
+        let queenside = Move::new("e1".into(), "c1".into(), Piece::WhiteKing);
+
+        assert_eq!(board.parse_uci_move("e1g1"), Ok(kingside));
+        assert_eq!(board.parse_uci_move("e1h1"), Ok(kingside));
+        assert_eq!(board.parse_uci_move("e1c1"), Ok(queenside));
+        assert_eq!(board.parse_uci_move("e1a1"), Ok(queenside));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut board = Board::default();
+        for uci in &["e2e4", "c7c5", "g1f3", "d7d6", "f1b5", "c8d7", "e1g1"] {
+            let m = board.parse_uci_move(uci).unwrap();
+            assert_eq!(&m.to_uci(), uci);
+            board.make_move(m);
+        }
+
+        assert_eq!(board.piece_at("g1"), Piece::WhiteKing);
+        assert_eq!(board.piece_at("f1"), Piece::WhiteRook);
+    }
+}
@@ -0,0 +1,154 @@
+use board::{Board, Color, Piece};
+use position::Position;
+use square::Square;
+
+const PIECES: usize = 0;
+const SIDE: usize = 12 * 64;
+const CASTLING: usize = SIDE + 1;
+const EN_PASSANT: usize = CASTLING + 4;
+
+// One random key per piece on each square, then the side to move, the four
+// castling rights and the eight en passant files.
+static KEYS: [u64; EN_PASSANT + 8] = keys();
+
+const fn keys() -> [u64; EN_PASSANT + 8] {
+    let mut keys = [0; EN_PASSANT + 8];
+    let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+    let mut i = 0;
+
+    // splitmix64, so the keys are the same on every run.
+    while i < keys.len() {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+
+    keys
+}
+
+fn piece_key(piece: Piece, position: Position) -> u64 {
+    let index = match piece {
+        Piece::WhitePawn => 0,
+        Piece::WhiteKnight => 1,
+        Piece::WhiteBishop => 2,
+        Piece::WhiteRook => 3,
+        Piece::WhiteQueen => 4,
+        Piece::WhiteKing => 5,
+        Piece::BlackPawn => 6,
+        Piece::BlackKnight => 7,
+        Piece::BlackBishop => 8,
+        Piece::BlackRook => 9,
+        Piece::BlackQueen => 10,
+        Piece::BlackKing => 11,
+        Piece::Empty => return 0,
+    };
+
+    KEYS[PIECES + index * 64 + Square::from(position).index()]
+}
+
+impl Board {
+    /// A Zobrist hash of everything that makes two positions the same for
+    /// the repetition rule: the pieces, the side to move, castling rights
+    /// and the en passant square.
+    pub fn hash(&self) -> u64 {
+        let mut hash = self.pawn_hash() ^ self.piece_hash(|piece| !is_pawn(piece));
+
+        if self.to_move == Color::Black {
+            hash ^= KEYS[SIDE];
+        }
+
+        let rights = self.castling();
+        let castling = [
+            rights.white_kingside,
+            rights.white_queenside,
+            rights.black_kingside,
+            rights.black_queenside,
+        ];
+        for (i, &allowed) in castling.iter().enumerate() {
+            if allowed {
+                hash ^= KEYS[CASTLING + i];
+            }
+        }
+
+        if let Some(position) = self.en_passant() {
+            let file: i8 = position.column.into();
+            hash ^= KEYS[EN_PASSANT + file as usize - 1];
+        }
+
+        hash
+    }
+
+    /// The part of `hash` that covers only the pawns, for caching pawn
+    /// structure.
+    pub fn pawn_hash(&self) -> u64 {
+        self.piece_hash(is_pawn)
+    }
+
+    fn piece_hash<F: Fn(Piece) -> bool>(&self, include: F) -> u64 {
+        self.enumerate_pieces()
+            .filter(|&(_, &piece)| include(piece))
+            .fold(0, |hash, (i, &piece)| {
+                hash ^ piece_key(piece, Position::from(i))
+            })
+    }
+}
+
+fn is_pawn(piece: Piece) -> bool {
+    piece == Piece::WhitePawn || piece == Piece::BlackPawn
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(board: &mut Board, moves: &[&str]) {
+        for san in moves {
+            let m = board.parse_san_move(san).unwrap();
+            board.make_move(m);
+        }
+    }
+
+    #[test]
+    fn transpositions_match() {
+        let mut one = Board::default();
+        let mut other = Board::default();
+        play(&mut one, &["Nf3", "Nf6", "g3"]);
+        play(&mut other, &["g3", "Nf6", "Nf3"]);
+
+        assert_eq!(one.hash(), other.hash());
+        assert_ne!(one.hash(), Board::default().hash());
+
+        play(&mut one, &["Ng8", "Ng1", "Nf6"]);
+        assert_ne!(one.hash(), other.hash());
+    }
+
+    #[test]
+    fn repetition() {
+        let start = Board::default();
+        let mut board = Board::default();
+        play(&mut board, &["Nf3", "Nf6", "Ng1", "Ng8"]);
+        assert_eq!(board.hash(), start.hash());
+    }
+
+    #[test]
+    fn state_changes_the_hash() {
+        let mut board = Board::default();
+        play(&mut board, &["e4"]);
+
+        let mut without_en_passant = board.clone();
+        without_en_passant.set_en_passant(None);
+        assert_ne!(board.hash(), without_en_passant.hash());
+
+        let mut other_side = without_en_passant.clone();
+        other_side.to_move = Color::White;
+        assert_ne!(other_side.hash(), without_en_passant.hash());
+
+        let mut no_castling = without_en_passant.clone();
+        no_castling.set_castling(::board::CastlingRights::none());
+        assert_ne!(no_castling.hash(), without_en_passant.hash());
+        assert_eq!(no_castling.pawn_hash(), board.pawn_hash());
+    }
+}